@@ -0,0 +1,5763 @@
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    ops::Range,
+    path::PathBuf,
+    rc::Rc,
+    sync::{
+        Arc, LazyLock, Mutex, OnceLock,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use chrono::{Datelike, Timelike};
+use dioxus::prelude::*;
+use typst::{
+    Document, Library, LibraryExt, WorldExt,
+    comemo::Track,
+    diag::{FileError, FileResult, PackageError, SourceDiagnostic, SourceResult, Warned},
+    ecow::{EcoString, EcoVec, eco_format},
+    engine::Sink,
+    foundations::{
+        Bytes, Datetime, Dict, FromValue, IntoValue, LocatableSelector, NativeElement, Repr, Scope,
+        Selector, Smart,
+    },
+    introspection::{Introspector, MetadataElem, Tag},
+    layout::{Dir, Frame, FrameItem},
+    math::EquationElem,
+    model::{BibliographyElem, DocumentInfo, HeadingElem, Outlinable, RefElem},
+    syntax::{
+        LinkedNode, Span, SyntaxMode, SyntaxNode, VirtualPath,
+        ast::{self, AstNode},
+        package::PackageVersion,
+    },
+    text::{FontBook, FontFamily, FontList, FontStyle, FontVariant, TextElem},
+    utils::LazyHash,
+    visualize::Color,
+};
+use typst_html::{HtmlAttr, HtmlElement, HtmlNode};
+use web_time::Instant;
+
+use crate::{
+    CompileError, CompiledDocument, Diagnostic, DocumentMetadata, OutlineEntry, PageStyle,
+};
+
+// The Typst types this crate's API takes or returns, so they can be named
+// without depending on the exact `typst` version this crate was built with.
+pub use typst::{
+    Feature, World,
+    foundations::Value,
+    layout::{Abs, PagedDocument},
+    syntax::{
+        FileId, Source,
+        package::{PackageManifest, PackageSpec},
+    },
+    text::Font,
+};
+pub use typst_html::HtmlDocument;
+// Everything else, such as what a custom `World` needs.
+pub use {typst, typst_html};
+
+/// Parses a bibliography in the format its extension names, or returns `None`
+/// if the extension isn't `.bib`, `.yml`, or `.yaml`.
+fn parse_bibliography(path: &str, text: &str) -> Option<Result<hayagriva::Library, String>> {
+    let extension = path
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("bib") => Some(hayagriva::io::from_biblatex_str(text).map_err(|errors| {
+            let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+            messages.join(", ")
+        })),
+        Some("yml" | "yaml") => Some(hayagriva::io::from_yaml_str(text).map_err(|e| e.to_string())),
+        _ => None,
+    }
+}
+
+/// Normalizes a path to ensure it starts with a leading slash.
+fn normalize_path(path: String) -> String {
+    if path.starts_with('/') {
+        path
+    } else {
+        format!("/{path}")
+    }
+}
+
+/// Options for configuring Typst compilation.
+///
+/// Use this to provide additional files (images, bibliographies, data files) and
+/// pre-loaded packages to the Typst compiler.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::CompileOptions;
+///
+/// # let csv_bytes: Vec<u8> = vec![];
+/// # let image_bytes: Vec<u8> = vec![];
+/// let options = CompileOptions::new()
+///     .with_file("data.csv", csv_bytes)
+///     .with_file("logo.png", image_bytes);
+/// ```
+///
+/// Settings held in an `Option` use their default while `None`, and an unset
+/// setting keeps the value from the other side in [`merge`](Self::merge).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompileOptions {
+    /// Files available to the Typst document, keyed by their virtual path.
+    pub files: HashMap<String, Vec<u8>>,
+    /// Pre-loaded packages, keyed by their package specification.
+    pub packages: HashMap<PackageSpec, HashMap<String, Vec<u8>>>,
+    /// Where packages are looked up, in order. Empty means only the pre-loaded
+    /// packages.
+    pub package_sources: Vec<PackageSource>,
+    /// Font files (TrueType, OpenType, or collections) available to the document.
+    pub fonts: Vec<Vec<u8>>,
+    /// Font families tried, in order, for glyphs the main font lacks.
+    pub fallback_fonts: Vec<String>,
+    /// Whether fonts installed on the operating system are available to the document.
+    pub system_fonts: Option<bool>,
+    /// Page setup for paged exports. Ignored by HTML export.
+    pub page: PageOptions,
+    /// Responsive wrapper placed around HTML output.
+    pub container: Option<ContainerOptions>,
+    /// Typst source inserted before every document.
+    pub preamble: Option<String>,
+    /// Typst source appended after every document.
+    pub postamble: Option<String>,
+    /// String values available to the document as `sys.inputs`.
+    pub inputs: HashMap<String, String>,
+    /// How HTML output is laid out.
+    pub format: Option<HtmlFormat>,
+    /// Whether standalone HTML embeds the fonts it uses as `@font-face` rules.
+    pub inline_fonts: Option<bool>,
+    /// Whether references to missing labels render as plain text instead of failing.
+    pub lenient_references: Option<bool>,
+    /// Whether equations are rendered as SVG labeled with their Typst source.
+    pub math_alt_text: Option<bool>,
+    /// How equations are represented in HTML output.
+    pub math: Option<MathRendering>,
+    /// Whether files that were not provided read as empty instead of failing.
+    pub missing_files_as_empty: Option<bool>,
+    /// Whether a missing file falls back to a provided file with a similar path.
+    pub fuzzy_paths: Option<bool>,
+    /// Directory relative paths in the main document resolve from, such as `/docs`.
+    pub root: Option<String>,
+    /// Wall-clock time a single compilation may take before it is abandoned.
+    pub time_budget: Option<Duration>,
+    /// Token that abandons compilations started with these options when cancelled.
+    pub cancellation: Option<CancellationToken>,
+    /// Where `datetime.today()` gets the current date from.
+    pub clock: Option<Clock>,
+}
+
+impl CompileOptions {
+    /// Creates a new empty `CompileOptions`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file to the compilation environment.
+    ///
+    /// Images added this way are inlined into the HTML output as `data:` URIs
+    /// when the document references them, see [`compile_html`]. Typst sources
+    /// can be brought in with `#import` or `#include`. Typst tracks the files
+    /// being evaluated, so an include that leads back to a file already in
+    /// progress, including the main document, fails with a "cyclic import"
+    /// error instead of recursing.
+    ///
+    /// No MIME type is needed for images. Typst picks the format from the
+    /// path's extension (`.svg`, `.png`, `.jpg`/`.jpeg`, `.gif`, `.webp`, `.pdf`)
+    /// and otherwise sniffs the file's contents, so extensionless files work as
+    /// long as the data is valid. A recognized but wrong extension wins over
+    /// the contents; the document can override it with `image(.., format: ..)`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::{compile_html, CompileOptions};
+    ///
+    /// # let svg = br#"<svg xmlns="http://www.w3.org/2000/svg" width="1" height="1"/>"#;
+    /// # let sniffed = CompileOptions::new().with_file("diagram", svg.to_vec());
+    /// # let html = compile_html(r#"#image("diagram")"#, &sniffed).unwrap();
+    /// # assert!(html.starts_with(r#"<img src="data:image/svg+xml;base64,"#));
+    /// # let mislabeled = CompileOptions::new().with_file("diagram.png", svg.to_vec());
+    /// # assert!(compile_html(r#"#image("diagram.png")"#, &mislabeled).is_err());
+    /// # let html = compile_html(r#"#image("diagram.png", format: "svg")"#, &mislabeled).unwrap();
+    /// # assert!(html.starts_with(r#"<img src="data:image/svg+xml;base64,"#));
+    /// # let png_bytes: Vec<u8> = vec![];
+    /// # let csv_bytes: Vec<u8> = vec![];
+    /// let options = CompileOptions::new()
+    ///     .with_file("figure.png", png_bytes)
+    ///     .with_file("data.csv", csv_bytes)
+    ///     .with_file("chapter.typ", b"= Chapter".to_vec());
+    /// # assert!(options.files.contains_key("/figure.png"));
+    /// # assert!(options.files.contains_key("/data.csv"));
+    /// let html = compile_html(r#"#include "chapter.typ""#, &options).unwrap();
+    /// # assert_eq!(html, "<h2>Chapter</h2>");
+    /// # let options = options.with_file("loop.typ", br#"#include "main.typ""#.to_vec());
+    /// # let error = compile_html(r#"#include "loop.typ""#, &options).unwrap_err();
+    /// # assert_eq!(error.messages(), ["cyclic import"]);
+    /// ```
+    #[must_use]
+    pub fn with_file(mut self, path: impl Into<String>, content: Vec<u8>) -> Self {
+        self.files.insert(normalize_path(path.into()), content);
+        self
+    }
+
+    /// Adds a bibliography file after checking that it parses.
+    ///
+    /// The file is added like [`with_file`](Self::with_file), for the document
+    /// to load with `#bibliography("/refs.bib")`. Its format follows the
+    /// extension: `.bib` for BibLaTeX, `.yml` or `.yaml` for Hayagriva. Checking
+    /// it here reports a broken file while assembling the options rather than
+    /// on every compilation. Citations and `@key` references link to their
+    /// entries in the rendered bibliography.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CompileError::Typst` if the file isn't UTF-8, fails to parse,
+    /// or has an extension other than those above.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::{compile_html, CompileOptions};
+    ///
+    /// let bib = br"@book{knuth, author = {Donald Knuth}, title = {The Art of Computer Programming}, year = {1968}}";
+    /// let options = CompileOptions::new().with_bibliography("refs.bib", bib.to_vec())?;
+    /// let html = compile_html("See @knuth.\n#bibliography(\"/refs.bib\")", &options)?;
+    /// # assert!(html.contains(r#"role="doc-biblioref">[1]</a>"#));
+    /// # assert!(CompileOptions::new().with_bibliography("refs.bib", b"@book{oops".to_vec()).is_err());
+    /// # assert!(CompileOptions::new().with_bibliography("refs.yml", b"knuth:\n  type: book\n  title: TAOCP".to_vec()).is_ok());
+    /// # assert!(CompileOptions::new().with_bibliography("refs.txt", Vec::new()).is_err());
+    /// # Ok::<(), dioxus_typst::CompileError>(())
+    /// ```
+    pub fn with_bibliography(
+        self,
+        path: impl Into<String>,
+        content: Vec<u8>,
+    ) -> Result<Self, CompileError> {
+        let path = path.into();
+        let text = std::str::from_utf8(&content)
+            .map_err(|_| CompileError::Typst(format!("{path} is not valid UTF-8")))?;
+        let Some(parsed) = parse_bibliography(&path, text) else {
+            return Err(CompileError::Typst(format!(
+                "{path} is not a .bib, .yml, or .yaml bibliography"
+            )));
+        };
+        parsed.map_err(|e| CompileError::Typst(format!("failed to parse {path}: {e}")))?;
+        Ok(self.with_file(path, content))
+    }
+
+    /// Adds a set of files mounted under a directory prefix.
+    ///
+    /// Every path in `files` is placed below `prefix`, so independent asset
+    /// bundles can share file names without overwriting each other.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::collections::HashMap;
+    /// use dioxus_typst::CompileOptions;
+    ///
+    /// # let logo_a: Vec<u8> = vec![];
+    /// # let logo_b: Vec<u8> = vec![];
+    /// let options = CompileOptions::new()
+    ///     .with_mount("a", HashMap::from([("logo.png".to_string(), logo_a)]))
+    ///     .with_mount("/b/", HashMap::from([("/logo.png".to_string(), logo_b)]));
+    /// # assert!(options.files.contains_key("/a/logo.png"));
+    /// # assert!(options.files.contains_key("/b/logo.png"));
+    /// ```
+    #[must_use]
+    pub fn with_mount(
+        mut self,
+        prefix: impl Into<String>,
+        files: HashMap<String, Vec<u8>>,
+    ) -> Self {
+        let prefix = normalize_path(prefix.into());
+        let prefix = prefix.trim_end_matches('/');
+        for (path, content) in files {
+            let path = normalize_path(path);
+            self.files.insert(format!("{prefix}{path}"), content);
+        }
+        self
+    }
+
+    /// Adds a pre-loaded package to the compilation environment.
+    ///
+    /// Paths are taken relative to the package root, with or without a leading
+    /// slash. When two keys name the same file, such as `lib.typ` and
+    /// `/lib.typ`, only one of them is kept and which one is unspecified; use
+    /// [`with_package_bytes_map`](Self::with_package_bytes_map) to reject such
+    /// maps instead.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::{package_spec, CompileOptions};
+    ///
+    /// # let mut package_files = std::collections::HashMap::new();
+    /// let options = CompileOptions::new()
+    ///     .with_package(package_spec("preview", "cetz", "0.2.2").unwrap(), package_files);
+    /// # let manifest = b"[package]\nname = \"demo\"\nversion = \"0.1.0\"\nentrypoint = \"lib.typ\"";
+    /// # let files = std::collections::HashMap::from([
+    /// #     ("typst.toml".to_string(), manifest.to_vec()),
+    /// #     ("lib.typ".to_string(), b"#include \"parts\"".to_vec()),
+    /// #     ("parts/intro.typ".to_string(), b"Intro".to_vec()),
+    /// # ]);
+    /// # let options = options.with_package(package_spec("preview", "demo", "0.1.0").unwrap(), files);
+    /// # let error = dioxus_typst::compile_html("#import \"@preview/demo:0.1.0\"", &options).unwrap_err();
+    /// # assert!(error.to_string().contains("is a directory"), "{error}");
+    /// ```
+    #[must_use]
+    pub fn with_package(mut self, spec: PackageSpec, files: HashMap<String, Vec<u8>>) -> Self {
+        let files = files
+            .into_iter()
+            .map(|(path, content)| (normalize_path(path), content))
+            .collect();
+        self.packages.insert(spec, files);
+        self
+    }
+
+    /// Adds a pre-loaded package like [`with_package`](Self::with_package),
+    /// failing if two keys name the same file.
+    ///
+    /// Use this for package maps from untrusted or generated sources, where
+    /// `lib.typ` and `/lib.typ` may both appear and silently keeping only one
+    /// would load the wrong file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CompileError::Typst` naming both keys when two of them refer
+    /// to the same path.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::collections::HashMap;
+    /// use dioxus_typst::{package_spec, CompileOptions};
+    ///
+    /// let spec = package_spec("preview", "demo", "0.1.0")?;
+    /// let files = HashMap::from([
+    ///     ("lib.typ".to_string(), b"#let a = 1".to_vec()),
+    ///     ("/lib.typ".to_string(), b"#let a = 2".to_vec()),
+    /// ]);
+    /// let result = CompileOptions::new().with_package_bytes_map(spec, files);
+    /// # let error = result.unwrap_err().to_string();
+    /// # assert!(error.contains("both `/lib.typ` and `lib.typ`"), "{error}");
+    /// # let spec = package_spec("preview", "demo", "0.1.0")?;
+    /// # let files = HashMap::from([("lib.typ".to_string(), Vec::new())]);
+    /// # let options = CompileOptions::new().with_package_bytes_map(spec.clone(), files)?;
+    /// # assert!(options.packages[&spec].contains_key("/lib.typ"));
+    /// # Ok::<(), dioxus_typst::CompileError>(())
+    /// ```
+    pub fn with_package_bytes_map(
+        mut self,
+        spec: PackageSpec,
+        files: HashMap<String, Vec<u8>>,
+    ) -> Result<Self, CompileError> {
+        let mut keys: HashMap<String, String> = HashMap::new();
+        let mut normalized = HashMap::new();
+        for (path, content) in files {
+            let key = normalize_path(path.clone());
+            if let Some(other) = keys.insert(key.clone(), path.clone()) {
+                let (first, second) = if other < path {
+                    (other, path)
+                } else {
+                    (path, other)
+                };
+                return Err(CompileError::Typst(format!(
+                    "package {spec} has both `{first}` and `{second}`, which name the same file"
+                )));
+            }
+            normalized.insert(key, content);
+        }
+        self.packages.insert(spec, normalized);
+        Ok(self)
+    }
+
+    /// Adds every package in a zip archive as pre-loaded packages.
+    ///
+    /// The archive holds one tree per package, laid out like Typst's package
+    /// store as `{namespace}/{name}/{version}/...`, with or without an `@`
+    /// before the namespace. Embedding such an archive with `include_bytes!`
+    /// makes all of a binary's packages available offline. Packages replace
+    /// earlier ones with the same specification, like
+    /// [`with_package`](Self::with_package).
+    ///
+    /// Requires the `zip` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CompileError::Typst` if the archive can't be read, or a file in
+    /// it is not inside a valid `{namespace}/{name}/{version}` directory.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::{compile_html, CompileOptions};
+    ///
+    /// # use std::io::Write;
+    /// # let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    /// # let manifest = "[package]\nname = \"greet\"\nversion = \"0.1.0\"\nentrypoint = \"lib.typ\"\n";
+    /// # let file = zip::write::SimpleFileOptions::default();
+    /// # zip.add_directory("@local/greet/0.1.0/", file).unwrap();
+    /// # zip.start_file("@local/greet/0.1.0/typst.toml", file).unwrap();
+    /// # zip.write_all(manifest.as_bytes()).unwrap();
+    /// # zip.start_file("@local/greet/0.1.0/lib.typ", file).unwrap();
+    /// # zip.write_all(b"#let hello = [Hello from the archive]").unwrap();
+    /// # let archive = zip.finish().unwrap().into_inner();
+    /// // let archive = include_bytes!("../packages.zip");
+    /// let options = CompileOptions::new().with_packages_from_zip(&archive)?;
+    /// let html = compile_html("#import \"@local/greet:0.1.0\": hello\n#hello", &options)?;
+    /// # assert_eq!(html, "<p>Hello from the archive</p>");
+    /// # let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    /// # zip.start_file("README.md", file).unwrap();
+    /// # let stray = zip.finish().unwrap().into_inner();
+    /// # assert!(CompileOptions::new().with_packages_from_zip(&stray).is_err());
+    /// # assert!(CompileOptions::new().with_packages_from_zip(b"not a zip").is_err());
+    /// # Ok::<(), dioxus_typst::CompileError>(())
+    /// ```
+    #[cfg(feature = "zip")]
+    pub fn with_packages_from_zip(mut self, archive: &[u8]) -> Result<Self, CompileError> {
+        use std::io::Read;
+
+        let error = |e: zip::result::ZipError| CompileError::Typst(format!("invalid zip: {e}"));
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(archive)).map_err(error)?;
+        let mut packages: HashMap<PackageSpec, HashMap<String, Vec<u8>>> = HashMap::new();
+        for index in 0..zip.len() {
+            let mut entry = zip.by_index(index).map_err(error)?;
+            if entry.is_dir() {
+                continue;
+            }
+            let name = entry.name().to_string();
+            let mut parts = name.trim_start_matches('@').splitn(4, '/');
+            let (Some(namespace), Some(package), Some(version), Some(path)) =
+                (parts.next(), parts.next(), parts.next(), parts.next())
+            else {
+                return Err(CompileError::Typst(format!(
+                    "{name} is not inside a {{namespace}}/{{name}}/{{version}} directory"
+                )));
+            };
+            let spec = package_spec(namespace, package, version)
+                .map_err(|e| CompileError::Typst(format!("{name}: {e}")))?;
+            let mut content = Vec::new();
+            entry
+                .read_to_end(&mut content)
+                .map_err(|e| CompileError::Typst(format!("failed to read {name}: {e}")))?;
+            packages
+                .entry(spec)
+                .or_default()
+                .insert(path.to_string(), content);
+        }
+        for (spec, files) in packages {
+            self = self.with_package(spec, files);
+        }
+        Ok(self)
+    }
+
+    /// Appends a place to look up packages imported by the document.
+    ///
+    /// Sources are tried in the order they were added, and a package comes
+    /// entirely from the first source that has it, so files of different
+    /// copies are never mixed. Without any sources, only packages added with
+    /// [`with_package`](Self::with_package) are available; once sources are
+    /// set, include [`PackageSource::Preloaded`] to keep consulting them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::{compile_html, CompileOptions, PackageSource};
+    ///
+    /// let vendor = std::env::temp_dir().join("dioxus-typst-vendor");
+    /// # let vendor = vendor.with_file_name(format!("dioxus-typst-vendor-{}", std::process::id()));
+    /// # let cleanup = vendor.clone();
+    /// let package = vendor.join("local/greet/0.1.0");
+    /// std::fs::create_dir_all(&package).unwrap();
+    /// std::fs::write(
+    ///     package.join("typst.toml"),
+    ///     "[package]\nname = \"greet\"\nversion = \"0.1.0\"\nentrypoint = \"lib.typ\"\n",
+    /// )
+    /// .unwrap();
+    /// std::fs::write(package.join("lib.typ"), "#let hello = [Hello from disk]").unwrap();
+    ///
+    /// let options = CompileOptions::new()
+    ///     .with_package_source(PackageSource::Directory(vendor))
+    ///     .with_package_source(PackageSource::Preloaded);
+    /// let source = r#"#import "@local/greet:0.1.0": hello
+    /// #hello"#;
+    /// let html = compile_html(source, &options).unwrap();
+    /// # assert_eq!(html, "<p>Hello from disk</p>");
+    /// # let missing = r#"#import "@local/absent:0.1.0""#;
+    /// # assert!(compile_html(missing, &options).is_err());
+    /// # // A copy updated in place without its manifest is not served.
+    /// # let stale = package.with_file_name("0.2.0");
+    /// # std::fs::create_dir_all(&stale).unwrap();
+    /// # std::fs::copy(package.join("typst.toml"), stale.join("typst.toml")).unwrap();
+    /// # std::fs::write(stale.join("lib.typ"), "#let hello = [Stale]").unwrap();
+    /// # let newer = r#"#import "@local/greet:0.2.0": hello
+    /// # #hello"#;
+    /// # let error = compile_html(newer, &options).unwrap_err().to_string();
+    /// # assert!(error.contains("mismatched version 0.1.0"), "{error}");
+    /// # let manifest = b"[package]\nname = \"greet\"\nversion = \"0.2.0\"\nentrypoint = \"lib.typ\"\n";
+    /// # let files = std::collections::HashMap::from([
+    /// #     ("typst.toml".to_string(), manifest.to_vec()),
+    /// #     ("lib.typ".to_string(), b"#let hello = [Fresh]".to_vec()),
+    /// # ]);
+    /// # let spec = dioxus_typst::package_spec("local", "greet", "0.2.0").unwrap();
+    /// # let options = options.with_package(spec, files);
+    /// # assert_eq!(compile_html(newer, &options).unwrap(), "<p>Fresh</p>");
+    /// # std::fs::remove_dir_all(&cleanup).unwrap();
+    /// ```
+    #[must_use]
+    pub fn with_package_source(mut self, source: PackageSource) -> Self {
+        self.package_sources.push(source);
+        self
+    }
+
+    /// Adds a font file to the compilation environment.
+    ///
+    /// Every face of a font collection is registered. Fonts are used for paged
+    /// exports and for content Typst renders as SVG inside the HTML output.
+    /// Faces that fail to parse are skipped; [`font_load_errors`] reports them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::CompileOptions;
+    ///
+    /// # let font_bytes: Vec<u8> = vec![];
+    /// let options = CompileOptions::new().with_font(font_bytes);
+    /// # assert_eq!(options.fonts.len(), 1);
+    /// ```
+    #[must_use]
+    pub fn with_font(mut self, content: Vec<u8>) -> Self {
+        self.fonts.push(content);
+        self
+    }
+
+    /// Adds every font file found in a directory and its subdirectories.
+    ///
+    /// Files ending in `.ttf`, `.otf`, `.ttc`, or `.otc` are read right away and
+    /// added as if by [`with_font`](Self::with_font), in path order, so every
+    /// face of a collection is registered. Other files, and entries that can't
+    /// be read, are skipped. Symlinks are followed only to targets inside the
+    /// directory, so a link out of it or back up it is skipped too. A font file
+    /// that turns out to be invalid shows up in [`font_load_errors`].
+    ///
+    /// Requires the `fonts-dir` feature, and is not available on WebAssembly.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::CompileOptions;
+    ///
+    /// let options = CompileOptions::new().with_fonts_dir("assets/fonts");
+    /// # let dir = std::env::temp_dir().join(format!("dioxus-typst-fonts-{}", std::process::id()));
+    /// # std::fs::create_dir_all(dir.join("serif")).unwrap();
+    /// # std::fs::write(dir.join("serif/Font.TTF"), b"ttf").unwrap();
+    /// # std::fs::write(dir.join("LICENSE.txt"), b"text").unwrap();
+    /// # let outside = dir.with_extension("outside.ttf");
+    /// # std::fs::write(&outside, b"outside").unwrap();
+    /// # #[cfg(unix)]
+    /// # {
+    /// #     std::os::unix::fs::symlink(&dir, dir.join("serif/loop")).unwrap();
+    /// #     std::os::unix::fs::symlink(&outside, dir.join("escape.ttf")).unwrap();
+    /// # }
+    /// # let options = CompileOptions::new().with_fonts_dir(&dir);
+    /// # std::fs::remove_file(&outside).unwrap();
+    /// # std::fs::remove_dir_all(&dir).unwrap();
+    /// # assert_eq!(options.fonts, [b"ttf".to_vec()]);
+    /// ```
+    #[cfg(all(feature = "fonts-dir", not(target_arch = "wasm32")))]
+    #[must_use]
+    pub fn with_fonts_dir(mut self, path: impl AsRef<std::path::Path>) -> Self {
+        const EXTENSIONS: [&str; 4] = ["ttf", "otf", "ttc", "otc"];
+
+        let mut files = Vec::new();
+        read_dir_recursive(path.as_ref(), &mut files);
+        files.sort();
+        for file in files {
+            let is_font = file
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| {
+                    EXTENSIONS.iter().any(|e| e.eq_ignore_ascii_case(extension))
+                });
+            if let Some(data) = is_font.then(|| std::fs::read(&file).ok()).flatten() {
+                self.fonts.push(data);
+            }
+        }
+        self
+    }
+
+    /// Sets the font families used, in order, for glyphs missing from the
+    /// document's font.
+    ///
+    /// The families are appended to Typst's default font list, so they apply to
+    /// any document that doesn't set its own `text(font: ..)`. They are also
+    /// moved to the front of the font book, so Typst's automatic fallback prefers
+    /// them over equally suitable fonts regardless of load order. This lets you
+    /// guarantee CJK or symbol coverage with specific fonts.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # if !cfg!(feature = "embed-fonts") { return; }
+    /// use dioxus_typst::{used_fonts, CompileOptions};
+    ///
+    /// let options = CompileOptions::new().with_fallback_fonts(["DejaVu Sans Mono"]);
+    /// let fonts = used_fonts("Box drawing: ┌─┐", &options).unwrap();
+    /// # let families: Vec<&str> = fonts.iter().map(|f| f.info().family.as_str()).collect();
+    /// # assert_eq!(families, ["Libertinus Serif", "DejaVu Sans Mono"]);
+    /// ```
+    #[must_use]
+    pub fn with_fallback_fonts<I, S>(mut self, families: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.fallback_fonts = families.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Makes the fonts installed on the operating system available to the document.
+    ///
+    /// The system is scanned once, on the first compilation that asks for it, and
+    /// the result is reused afterwards. System fonts come after fonts added with
+    /// [`with_font`](Self::with_font) and before the embedded ones, so
+    /// `#set text(font: "Arial")` picks up an installed Arial.
+    ///
+    /// Fonts are only loaded with the `system-fonts` feature; without it, the
+    /// setting is kept but has no effect, so shared configuration still builds.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::CompileOptions;
+    ///
+    /// let options = CompileOptions::new().with_system_fonts(true);
+    /// # assert_eq!(options.system_fonts, Some(true));
+    /// ```
+    #[must_use]
+    pub fn with_system_fonts(mut self, enabled: bool) -> Self {
+        self.system_fonts = Some(enabled);
+        self
+    }
+
+    /// Sets the page setup used by paged exports.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::{Abs, CompileOptions, Orientation, PageOptions};
+    ///
+    /// let options = CompileOptions::new().with_page(PageOptions {
+    ///     paper: Some("a5".to_string()),
+    ///     margin: Some(Abs::mm(15.0)),
+    ///     orientation: Some(Orientation::Landscape),
+    ///     ..Default::default()
+    /// });
+    /// ```
+    #[must_use]
+    pub fn with_page(mut self, page: PageOptions) -> Self {
+        self.page = page;
+        self
+    }
+
+    /// Wraps HTML output in a responsive container.
+    ///
+    /// See [`ContainerOptions`] for the emitted markup and the CSS variables
+    /// that let a stylesheet adjust it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::{compile_full_html, compile_html, CompileOptions, ContainerOptions};
+    ///
+    /// let options = CompileOptions::new().with_container(ContainerOptions {
+    ///     max_width: Some("60rem".to_string()),
+    ///     columns: Some(2),
+    ///     column_width: Some("22rem".to_string()),
+    /// });
+    /// let html = compile_html("Text", &options).unwrap();
+    /// # assert_eq!(
+    /// #     html,
+    /// #     "<div class=\"typst-container\" style=\"box-sizing: border-box; \
+    /// #      max-width: var(--typst-max-width, 60rem); margin-inline: auto; \
+    /// #      columns: var(--typst-columns, 2) var(--typst-column-width, 22rem); \
+    /// #      column-gap: var(--typst-column-gap, 2em)\"><p>Text</p></div>",
+    /// # );
+    /// # let page = compile_full_html("Text", &options).unwrap();
+    /// # assert!(page.starts_with("<!DOCTYPE html>"));
+    /// # assert!(page.contains("<body>\n    <div class=\"typst-container\""));
+    /// # assert!(page.contains("<p>Text</p></div>\n  </body>"));
+    /// ```
+    #[must_use]
+    pub fn with_container(mut self, container: ContainerOptions) -> Self {
+        self.container = Some(container);
+        self
+    }
+
+    /// Sets Typst source that is inserted before every document.
+    ///
+    /// Use this for shared setup such as `#set` rules and imports that should
+    /// apply to every document without appearing in its source. The preamble
+    /// is placed on its own lines ahead of the document.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::{compile_html, CompileOptions};
+    ///
+    /// let options = CompileOptions::new()
+    ///     .with_preamble("#show heading: set text(blue)\n#let house = [Typst Inc.]")
+    ///     .with_postamble("#line()\nPublished by #house");
+    /// let html = compile_html("= Report", &options).unwrap();
+    /// # assert!(html.contains("<h2>Report</h2>"));
+    /// # assert!(html.contains("Published by Typst Inc."));
+    /// ```
+    #[must_use]
+    pub fn with_preamble(mut self, preamble: impl Into<String>) -> Self {
+        self.preamble = Some(preamble.into());
+        self
+    }
+
+    /// Sets Typst source that is appended after every document.
+    ///
+    /// The postamble is placed on its own lines after the document. See
+    /// [`CompileOptions::with_preamble`] for an example.
+    #[must_use]
+    pub fn with_postamble(mut self, postamble: impl Into<String>) -> Self {
+        self.postamble = Some(postamble.into());
+        self
+    }
+
+    /// Sets whether files that were not provided read as empty.
+    ///
+    /// Templates can then reference optional assets and check for them with
+    /// `read(..).len()` instead of failing the whole compilation when an
+    /// asset isn't supplied. An `#include` of a missing file includes nothing.
+    /// Files inside packages are unaffected.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::{compile_html, CompileOptions};
+    ///
+    /// let source = r#"
+    /// #let logo = read("/logo.svg", encoding: none)
+    /// #if logo.len() > 0 { image(logo) } else [No logo]
+    /// "#;
+    /// let options = CompileOptions::new().with_missing_files_as_empty(true);
+    /// let html = compile_html(source, &options).unwrap();
+    /// # assert_eq!(html, "<p>No logo</p>");
+    /// # assert!(compile_html(source, &CompileOptions::new()).is_err());
+    /// # let html = compile_html(r#"A#include "/missing.typ""#, &options).unwrap();
+    /// # assert_eq!(html, "<p>A</p>");
+    /// ```
+    #[must_use]
+    pub fn with_missing_files_as_empty(mut self, enabled: bool) -> Self {
+        self.missing_files_as_empty = Some(enabled);
+        self
+    }
+
+    /// Sets whether a path that doesn't match any provided file exactly falls
+    /// back to a provided file with a similar path.
+    ///
+    /// Documents written against another directory layout often refer to
+    /// `logo.png` from inside `chapters/`, or to `/assets/logo.png` when the
+    /// file was added as `/logo.png`. With this enabled, a missing path first
+    /// matches the only provided file with the same path ignoring case, then
+    /// the only one with the same name ignoring case. Several files sharing
+    /// the path or name are ambiguous and still fail. Files inside packages
+    /// are unaffected.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::{compile_html, CompileOptions};
+    ///
+    /// let options = CompileOptions::new()
+    ///     .with_file("Notes.typ", b"Notes".to_vec())
+    ///     .with_fuzzy_paths(true);
+    /// let html = compile_html(r#"#include "/chapters/notes.typ""#, &options).unwrap();
+    /// # assert_eq!(html, "<p>Notes</p>");
+    /// # let options = options.with_file("/old/notes.typ", b"Old".to_vec());
+    /// # assert!(compile_html(r#"#include "/chapters/notes.typ""#, &options).is_err());
+    /// # assert_eq!(compile_html(r#"#include "/notes.typ""#, &options).unwrap(), "<p>Notes</p>");
+    /// # let options = options.with_file("/NOTES.typ", b"Shouting".to_vec());
+    /// # assert!(compile_html(r#"#include "/Notes.TYP""#, &options).is_err());
+    /// ```
+    #[must_use]
+    pub fn with_fuzzy_paths(mut self, enabled: bool) -> Self {
+        self.fuzzy_paths = Some(enabled);
+        self
+    }
+
+    /// Adds a date and time the document can read from `sys.inputs`, formatted
+    /// as ISO 8601.
+    ///
+    /// The value is an RFC 3339 timestamp with whole seconds, such as
+    /// `2026-03-01T09:30:00+01:00`, or with `Z` for UTC. Every caller gets the
+    /// same fixed-width representation, so the document can rely on its layout,
+    /// for example taking the date from the first ten characters.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use chrono::DateTime;
+    /// use dioxus_typst::{compile_html, CompileOptions};
+    ///
+    /// let built = DateTime::parse_from_rfc3339("2026-03-01T09:30:00+01:00").unwrap();
+    /// let options = CompileOptions::new().with_datetime_input("built", built);
+    /// let source = r#"#let (y, m, d) = sys.inputs.built.slice(0, 10).split("-").map(int)
+    /// Built #datetime(year: y, month: m, day: d).display("[day].[month].[year]")"#;
+    /// let html = compile_html(source, &options).unwrap();
+    /// # assert_eq!(html, "<p>Built 01.03.2026</p>");
+    /// # assert_eq!(options.inputs["built"], "2026-03-01T09:30:00+01:00");
+    /// # let utc = built.with_timezone(&chrono::Utc).fixed_offset();
+    /// # let options = CompileOptions::new().with_datetime_input("built", utc);
+    /// # assert_eq!(options.inputs["built"], "2026-03-01T08:30:00Z");
+    /// ```
+    #[must_use]
+    pub fn with_datetime_input(
+        self,
+        key: impl Into<String>,
+        datetime: chrono::DateTime<chrono::FixedOffset>,
+    ) -> Self {
+        let value = datetime.to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        self.with_input(key, value)
+    }
+
+    /// Sets the directory that relative paths in the document resolve from.
+    ///
+    /// Typst resolves relative paths from the directory of the file containing
+    /// them, and the main document otherwise sits at the root of the virtual
+    /// file system. With a root such as `docs`, it sits in `/docs` instead, so
+    /// `#image("images/logo.png")` loads `/docs/images/logo.png` from the
+    /// provided files. Absolute paths are unaffected. This lets a project be
+    /// ported with its files under their original paths. Diagnostics name the
+    /// main file `{root}/main.typ`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::{compile_html, CompileOptions};
+    ///
+    /// let options = CompileOptions::new()
+    ///     .with_file("docs/chapters/intro.typ", b"Welcome.".to_vec())
+    ///     .with_root("docs");
+    /// let html = compile_html(r#"#include "chapters/intro.typ""#, &options).unwrap();
+    /// # assert_eq!(html, "<p>Welcome.</p>");
+    /// # assert!(compile_html(r#"#include "/docs/chapters/intro.typ""#, &options).is_ok());
+    /// # assert!(compile_html(r#"#include "chapters/intro.typ""#, &CompileOptions::new()).is_err());
+    /// # assert_eq!(CompileOptions::new().with_root("/docs/").root.as_deref(), Some("/docs"));
+    /// ```
+    #[must_use]
+    pub fn with_root(mut self, root: impl Into<String>) -> Self {
+        let root = normalize_path(root.into());
+        self.root = Some(root.trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Adds a value the document can read from `sys.inputs`.
+    ///
+    /// This is the programmatic equivalent of `typst compile --input key=value`,
+    /// which some packages and templates use for configuration.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::{compile_html, CompileOptions};
+    ///
+    /// let options = CompileOptions::new().with_input("theme", "dark");
+    /// let html = compile_html("Theme: #sys.inputs.at(\"theme\")", &options).unwrap();
+    /// # assert_eq!(html, "<p>Theme: dark</p>");
+    /// ```
+    #[must_use]
+    pub fn with_input(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.inputs.insert(key.into(), value.into());
+        self
+    }
+
+    /// Sets whether HTML output is minified, as a shorthand for
+    /// [`with_format`](Self::with_format) with [`HtmlFormat::Minified`].
+    /// Turning it off goes back to [`HtmlFormat::Compact`], unless the format
+    /// is [`HtmlFormat::Pretty`], which is kept.
+    ///
+    /// Minification drops comments and collapses the indentation and line breaks
+    /// between elements, while leaving the contents of `<pre>`, `<textarea>`,
+    /// `<script>`, and `<style>` untouched. This shrinks HTML that is sent over
+    /// the network or stored.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::{compile_html, CompileOptions};
+    ///
+    /// let source = "= Title\n\nSome text.\n\n```\nindented\n    code\n```";
+    /// let html = compile_html(source, &CompileOptions::new().with_minify(true)).unwrap();
+    /// # assert_eq!(html, "<h2>Title</h2><p>Some text.</p><pre><code>indented<br>    code</code></pre>");
+    /// # use dioxus_typst::HtmlFormat;
+    /// # let pretty = CompileOptions::new().with_format(HtmlFormat::Pretty).with_minify(false);
+    /// # assert_eq!(pretty.format, Some(HtmlFormat::Pretty));
+    /// # let compact = CompileOptions::new().with_minify(true).with_minify(false);
+    /// # assert_eq!(compact.format, Some(HtmlFormat::Compact));
+    /// ```
+    #[must_use]
+    pub fn with_minify(mut self, minify: bool) -> Self {
+        if minify {
+            self.format = Some(HtmlFormat::Minified);
+        } else if self.format != Some(HtmlFormat::Pretty) {
+            self.format = Some(HtmlFormat::Compact);
+        }
+        self
+    }
+
+    /// Sets how HTML output is laid out.
+    ///
+    /// See [`HtmlFormat`] for the layouts. None of them change how the HTML
+    /// renders: whitespace is only added or removed between block-level
+    /// elements, never inside paragraphs or inline elements.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::{compile_html, CompileOptions, ContainerOptions, HtmlFormat};
+    ///
+    /// let source = "= Title\n\n- One\n- Two";
+    /// let options = CompileOptions::new().with_format(HtmlFormat::Pretty);
+    /// let html = compile_html(source, &options).unwrap();
+    /// # assert_eq!(html, "<h2>Title</h2>\n<ul>\n  <li>One</li>\n  <li>Two</li>\n</ul>");
+    /// # let compact = compile_html(source, &CompileOptions::new()).unwrap();
+    /// # assert_eq!(compact, "<h2>Title</h2>\n    <ul>\n      <li>One</li>\n      <li>Two</li>\n    </ul>");
+    /// # let contained = options.clone().with_container(ContainerOptions::default());
+    /// # let html = compile_html("= Title\n\nText", &contained).unwrap();
+    /// # assert!(html.starts_with("<div class=\"typst-container\""));
+    /// # assert!(html.ends_with("\">\n  <h2>Title</h2>\n  <p>Text</p>\n</div>"));
+    /// # let full = dioxus_typst::compile_full_html("= Title\n\nText", &contained).unwrap();
+    /// # assert!(full.contains("\">\n      <h2>Title</h2>\n      <p>Text</p>\n    </div>\n  </body>"), "{full}");
+    /// ```
+    #[must_use]
+    pub fn with_format(mut self, format: HtmlFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Embeds the fonts a standalone document uses in its `<head>`.
+    ///
+    /// Typst's HTML export leaves typefaces to the browser, so a document set in
+    /// a custom font renders in whatever the reader has installed. With this
+    /// enabled, [`compile_full_html`] embeds every font that contributed glyphs,
+    /// as found by [`used_fonts`], as a base64 `@font-face` rule, and sets the
+    /// `<body>` in those families in order of first use. The page then renders
+    /// in the right typeface offline and without serving font files. Finding the
+    /// used fonts takes an extra paged compilation, and HTML fragments are not
+    /// affected.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # if !cfg!(feature = "embed-fonts") { return; }
+    /// use dioxus_typst::{compile_full_html, CompileOptions};
+    ///
+    /// let options = CompileOptions::new().with_inline_fonts(true);
+    /// let source = r#"#set text(font: "DejaVu Sans Mono")
+    /// Hello"#;
+    /// let html = compile_full_html(source, &options).unwrap();
+    /// # assert!(html.contains(r#"@font-face { font-family: "DejaVu Sans Mono"; src: url("data:font/ttf;base64,"#));
+    /// # assert!(html.contains(r#"body { font-family: "DejaVu Sans Mono"; }</style>"#));
+    /// # assert!(!html.contains("Libertinus"));
+    /// # assert!(html.find("<style>").unwrap() < html.find("</head>").unwrap());
+    /// # assert!(!compile_full_html(source, &CompileOptions::new()).unwrap().contains("@font-face"));
+    /// ```
+    #[must_use]
+    pub fn with_inline_fonts(mut self, enabled: bool) -> Self {
+        self.inline_fonts = Some(enabled);
+        self
+    }
+
+    /// Sets whether references to missing labels are tolerated.
+    ///
+    /// Typst links every `@label` reference to its target with an
+    /// `<a href="#..">` anchor, and by default a reference whose label does not
+    /// exist fails the compilation. When lenient, such a reference is left as
+    /// plain `@label` text and reported as a warning instead, which suits
+    /// documents that are still being written. When the document has a
+    /// bibliography, references to keys in a provided `.bib`, `.yml`, or
+    /// `.yaml` file are still cited, see
+    /// [`with_bibliography`](Self::with_bibliography).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::{compile_html, CompileOptions};
+    ///
+    /// let options = CompileOptions::new().with_lenient_references(true);
+    /// let html = compile_html("See @intro.", &options).unwrap();
+    /// # assert_eq!(html, "<p>See @intro.</p>");
+    /// # assert!(compile_html("See @intro.", &CompileOptions::new()).is_err());
+    /// # let bib = br"@book{knuth, author = {Donald Knuth}, title = {TAOCP}, year = {1968}}";
+    /// # let options = options.with_bibliography("refs.bib", bib.to_vec()).unwrap();
+    /// # let source = "See @intro and @knuth.\n#bibliography(\"/refs.bib\")";
+    /// # let document = dioxus_typst::compile(source, &options).unwrap();
+    /// # assert!(document.html.starts_with("<p>See @intro and <a"), "{}", document.html);
+    /// # assert!(document.html.contains(r#"role="doc-biblioref">[1]</a>"#));
+    /// # let dangling: Vec<_> = document.warnings.iter().filter(|w| w.message.starts_with("label")).collect();
+    /// # assert_eq!(dangling.len(), 1);
+    /// # assert_eq!(dangling[0].message, "label `<intro>` does not exist in the document");
+    /// ```
+    #[must_use]
+    pub fn with_lenient_references(mut self, lenient: bool) -> Self {
+        self.lenient_references = Some(lenient);
+        self
+    }
+
+    /// Sets whether equations are rendered with their source as alternative text.
+    ///
+    /// Typst's HTML export drops equations by default. With this enabled, each
+    /// equation is rendered as inline SVG inside a `<span>`, or a `<div>` for
+    /// block equations, with `role="math"`, the class `typst-math`, and an
+    /// `aria-label` holding the equation's Typst source, so screen readers
+    /// announce `x^2` rather than skipping the formula. The source is read
+    /// without the `$` delimiters, with whitespace collapsed, and escaped for
+    /// the attribute when the HTML is written.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # if !cfg!(feature = "embed-fonts") { return; }
+    /// use dioxus_typst::{compile_html, CompileOptions};
+    ///
+    /// let options = CompileOptions::new().with_math_alt_text(true);
+    /// let html = compile_html("Area: $pi r^2$\n$ a < b \"and\" c $", &options).unwrap();
+    /// # assert!(html.contains(r#"<span role="math" class="typst-math" aria-label="pi r^2"><svg"#));
+    /// # assert!(html.contains(r#"<div role="math" class="typst-math" aria-label="a < b &quot;and&quot; c">"#));
+    /// # assert!(!compile_html("$x$", &CompileOptions::new()).unwrap().contains("svg"));
+    /// ```
+    #[must_use]
+    pub fn with_math_alt_text(mut self, enabled: bool) -> Self {
+        self.math_alt_text = Some(enabled);
+        self
+    }
+
+    /// Chooses how equations are represented in HTML output.
+    ///
+    /// See [`MathRendering`] for the strategies and which of them the linked
+    /// Typst version supports. Unsupported strategies fall back as described by
+    /// [`MathRendering::resolve`]. Combined with
+    /// [`with_math_alt_text`](Self::with_math_alt_text), the chosen
+    /// representation also gets the equation's source as its `aria-label`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # if !cfg!(feature = "embed-fonts") { return; }
+    /// use dioxus_typst::{compile_html, CompileOptions, MathRendering};
+    ///
+    /// let options = CompileOptions::new().with_math(MathRendering::Source);
+    /// let html = compile_html("Area: $pi r^2$", &options).unwrap();
+    /// # assert!(html.contains(r#"<span role="math" class="typst-math">pi r^2</span>"#));
+    /// # let svg = CompileOptions::new().with_math(MathRendering::Svg);
+    /// # let html = compile_html("$ x $", &svg).unwrap();
+    /// # assert!(html.starts_with(r#"<div role="math" class="typst-math">"#) && html.contains("<svg"));
+    /// # assert!(!html.contains("aria-label"));
+    /// # let mathml = CompileOptions::new().with_math(MathRendering::MathMl);
+    /// # assert_eq!(compile_html("$ x $", &mathml).unwrap(), html);
+    /// ```
+    #[must_use]
+    pub fn with_math(mut self, rendering: MathRendering) -> Self {
+        self.math = Some(rendering);
+        self
+    }
+
+    /// Limits how long a single compilation may run.
+    ///
+    /// Typst offers no way to interrupt evaluation, so the budget is enforced
+    /// whenever the compiler requests a source, file, or font. Once it is spent,
+    /// those requests fail and compilation returns
+    /// `CompileError::Typst("compilation exceeded time budget")`. Pure computation
+    /// that never touches the world can still overrun the budget until it next
+    /// does. The budget is measured with the browser's clock on WebAssembly.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use dioxus_typst::{compile_html, CompileError, CompileOptions};
+    ///
+    /// let options = CompileOptions::new().with_time_budget(Duration::ZERO);
+    /// let result = compile_html("Hello", &options);
+    /// # assert_eq!(
+    /// #     result,
+    /// #     Err(CompileError::Typst("compilation exceeded time budget".to_string()))
+    /// # );
+    /// ```
+    #[must_use]
+    pub fn with_time_budget(mut self, budget: Duration) -> Self {
+        self.time_budget = Some(budget);
+        self
+    }
+
+    /// Attaches a token that abandons compilation when cancelled.
+    ///
+    /// Cancellation is checked at the same points as the time budget, see
+    /// [`CompileOptions::with_time_budget`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::{compile_html, CancellationToken, CompileError, CompileOptions};
+    ///
+    /// let token = CancellationToken::new();
+    /// let options = CompileOptions::new().with_cancellation(token.clone());
+    ///
+    /// // Typically called from another thread while compilation runs.
+    /// token.cancel();
+    /// # assert_eq!(
+    /// #     compile_html("Hello", &options),
+    /// #     Err(CompileError::Typst("compilation was cancelled".to_string()))
+    /// # );
+    /// ```
+    #[must_use]
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Sets where `datetime.today()` gets the current date from.
+    ///
+    /// Use [`Clock::Fixed`] to render documents as of a given moment, or
+    /// [`Clock::Disabled`] for reproducible builds where a document that reads
+    /// the current date should fail rather than change from run to run.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::{compile_html, Clock, CompileOptions};
+    ///
+    /// let date = chrono::DateTime::parse_from_rfc3339("2024-03-01T09:30:00+01:00").unwrap();
+    /// let options = CompileOptions::new().with_clock(Clock::Fixed(date));
+    /// let html = compile_html("#datetime.today().display()", &options).unwrap();
+    /// # assert_eq!(html, "<p>2024-03-01 09:30:00</p>");
+    /// # let html = compile_html("#datetime.today(offset: -10).display()", &options).unwrap();
+    /// # assert_eq!(html, "<p>2024-02-29 22:30:00</p>");
+    ///
+    /// let options = CompileOptions::new().with_clock(Clock::Disabled);
+    /// let error = compile_html("#datetime.today()", &options).unwrap_err();
+    /// # assert_eq!(error.messages(), ["unable to get the current date"]);
+    /// ```
+    #[must_use]
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Returns a digest of every option, including file and font contents.
+    ///
+    /// Options that compare equal have the same digest, so it can stand in for
+    /// the options as a cache or memo key. Equality and [`Hash`] both read every
+    /// file and font byte, which is noticeable with large assets; compute the
+    /// digest once after building the options and compare that instead. Because
+    /// the fields are public, the digest is not stored and must be recomputed
+    /// after changing them. It is only stable within one process.
+    ///
+    /// Typst's own memoization is independent of this: repeated compilations of
+    /// unchanged sources and files already reuse work, whatever the digest.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::CompileOptions;
+    ///
+    /// let a = CompileOptions::new().with_file("a.csv", vec![1; 1 << 20]);
+    /// let b = CompileOptions::new().with_file("a.csv", vec![1; 1 << 20]);
+    /// assert_eq!(a.content_hash(), b.content_hash());
+    /// # assert_ne!(a.content_hash(), a.clone().with_minify(true).content_hash());
+    /// # let c = a.clone().with_file("b.csv", vec![2]).with_file("c.csv", vec![3]);
+    /// # let d = a.clone().with_file("c.csv", vec![3]).with_file("b.csv", vec![2]);
+    /// # assert_eq!(c.content_hash(), d.content_hash());
+    /// ```
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Combines two sets of options, with `other` taking precedence.
+    ///
+    /// Files and packages are unioned, and `other` wins when both define the same
+    /// path or package. Fonts from `other` are appended after those in `self`.
+    /// Settings and page settings that `other` leaves unset keep the values
+    /// from `self`, so a later layer can turn off a flag an earlier one set.
+    ///
+    /// This makes layered configuration straightforward, such as app-wide
+    /// defaults extended with per-document files.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::CompileOptions;
+    ///
+    /// let defaults = CompileOptions::new()
+    ///     .with_file("logo.png", vec![1])
+    ///     .with_file("style.typ", vec![2]);
+    /// let document = CompileOptions::new().with_file("logo.png", vec![3]);
+    ///
+    /// let options = defaults.merge(document);
+    /// # assert_eq!(options.files["/logo.png"], vec![3]);
+    /// # assert_eq!(options.files["/style.typ"], vec![2]);
+    /// # let strict = CompileOptions::new().with_lenient_references(false).with_minify(false);
+    /// # let layered = CompileOptions::new().with_lenient_references(true).with_minify(true);
+    /// # let off = layered.clone().merge(strict);
+    /// # assert_eq!((off.lenient_references, off.format), (Some(false), Some(dioxus_typst::HtmlFormat::Compact)));
+    /// # assert_eq!(layered.clone().merge(CompileOptions::new()), layered);
+    /// ```
+    #[must_use]
+    pub fn merge(mut self, other: Self) -> Self {
+        self.files.extend(other.files);
+        self.packages.extend(other.packages);
+        if !other.package_sources.is_empty() {
+            self.package_sources = other.package_sources;
+        }
+        self.inputs.extend(other.inputs);
+        self.fonts.extend(other.fonts);
+        if !other.fallback_fonts.is_empty() {
+            self.fallback_fonts = other.fallback_fonts;
+        }
+        self.page = self.page.merge(other.page);
+        self.container = other.container.or(self.container);
+        self.system_fonts = other.system_fonts.or(self.system_fonts);
+        self.format = other.format.or(self.format);
+        self.inline_fonts = other.inline_fonts.or(self.inline_fonts);
+        self.lenient_references = other.lenient_references.or(self.lenient_references);
+        self.math_alt_text = other.math_alt_text.or(self.math_alt_text);
+        self.math = other.math.or(self.math);
+        self.missing_files_as_empty = other.missing_files_as_empty.or(self.missing_files_as_empty);
+        self.fuzzy_paths = other.fuzzy_paths.or(self.fuzzy_paths);
+        self.root = other.root.or(self.root);
+        self.preamble = other.preamble.or(self.preamble);
+        self.postamble = other.postamble.or(self.postamble);
+        self.time_budget = other.time_budget.or(self.time_budget);
+        self.cancellation = other.cancellation.or(self.cancellation);
+        self.clock = other.clock.or(self.clock);
+        self
+    }
+}
+
+/// A place packages are looked up in, see [`CompileOptions::with_package_source`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PackageSource {
+    /// The packages added with [`CompileOptions::with_package`].
+    Preloaded,
+    /// A directory laid out like Typst's local package store, with each package
+    /// in `{namespace}/{name}/{version}/`. A package whose `typst.toml` names
+    /// another package or version is skipped, and a file that is a symlink
+    /// pointing outside its package can't be read. Unavailable in the browser.
+    Directory(PathBuf),
+}
+
+/// How HTML output is laid out, see [`CompileOptions::with_format`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum HtmlFormat {
+    /// As Typst writes it: block-level elements on their own lines, indented
+    /// by nesting, with fragments keeping the indentation they had inside the
+    /// document's `<body>`.
+    #[default]
+    Compact,
+    /// Like [`Compact`](Self::Compact), but with fragments starting at the left
+    /// margin and the contents of a container indented inside it, for reading
+    /// the output while debugging.
+    Pretty,
+    /// Without comments and insignificant whitespace, see
+    /// [`CompileOptions::with_minify`].
+    Minified,
+}
+
+/// How equations are represented in HTML output, see
+/// [`CompileOptions::with_math`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum MathRendering {
+    /// Typst's own behavior: equations are left out with a warning, unless
+    /// [`CompileOptions::with_math_alt_text`] is set, which implies
+    /// [`Svg`](Self::Svg).
+    #[default]
+    Omit,
+    /// Inline SVG inside a `<span>`, or a `<div>` for block equations, with
+    /// `role="math"` and the class `typst-math`. Laying out equations takes a
+    /// math font, such as the ones the `embed-fonts` feature bundles.
+    Svg,
+    /// MathML elements. Typst 0.14 cannot export MathML, so this falls back to
+    /// [`Svg`](Self::Svg).
+    MathMl,
+    /// The equation's Typst source as text inside the same `<span>` or `<div>`
+    /// as [`Svg`](Self::Svg), for styling with plain CSS or a client-side
+    /// renderer.
+    Source,
+}
+
+impl MathRendering {
+    /// Returns the strategy actually used with the linked Typst version.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::MathRendering;
+    ///
+    /// assert_eq!(MathRendering::MathMl.resolve(), MathRendering::Svg);
+    /// # assert_eq!(MathRendering::Source.resolve(), MathRendering::Source);
+    /// ```
+    pub fn resolve(self) -> Self {
+        match self {
+            Self::MathMl => Self::Svg,
+            other => other,
+        }
+    }
+}
+
+/// The source of the current date for `datetime.today()`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Clock {
+    /// The system clock, in the local time zone. In the browser, that is the
+    /// browser's clock and time zone.
+    #[default]
+    System,
+    /// A fixed moment, converted to the offset the document asks for.
+    Fixed(chrono::DateTime<chrono::FixedOffset>),
+    /// No clock: `datetime.today()` fails with an error.
+    Disabled,
+}
+
+/// Map fields are hashed independently of iteration order, matching their
+/// [`PartialEq`].
+impl Hash for CompileOptions {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_unordered(state, &self.files);
+        hash_unordered(
+            state,
+            self.packages.iter().map(|(spec, files)| {
+                let mut hasher = DefaultHasher::new();
+                hash_unordered(&mut hasher, files);
+                (spec, hasher.finish())
+            }),
+        );
+        self.package_sources.hash(state);
+        self.fonts.hash(state);
+        self.fallback_fonts.hash(state);
+        self.system_fonts.hash(state);
+        self.page.hash(state);
+        self.container.hash(state);
+        self.preamble.hash(state);
+        self.postamble.hash(state);
+        hash_unordered(state, &self.inputs);
+        self.format.hash(state);
+        self.inline_fonts.hash(state);
+        self.lenient_references.hash(state);
+        self.math_alt_text.hash(state);
+        self.math.hash(state);
+        self.missing_files_as_empty.hash(state);
+        self.fuzzy_paths.hash(state);
+        self.root.hash(state);
+        self.time_budget.hash(state);
+        self.cancellation.hash(state);
+        self.clock.hash(state);
+    }
+}
+
+/// Hashes a collection so that the order of its items doesn't matter.
+fn hash_unordered<H: Hasher, T: Hash>(state: &mut H, items: impl IntoIterator<Item = T>) {
+    let mut sum = 0u64;
+    let mut len = 0usize;
+    for item in items {
+        let mut hasher = DefaultHasher::new();
+        item.hash(&mut hasher);
+        sum = sum.wrapping_add(hasher.finish());
+        len += 1;
+    }
+    len.hash(state);
+    sum.hash(state);
+}
+
+/// A handle for cancelling in-flight compilations from another thread.
+///
+/// Clones share the same state, so cancelling any clone cancels them all.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a token that has not been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels every compilation using this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns whether the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl PartialEq for CancellationToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CancellationToken {}
+
+/// Hashes the shared state's address, matching [`PartialEq`].
+impl Hash for CancellationToken {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0).hash(state);
+    }
+}
+
+/// Page orientation for paged exports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Orientation {
+    /// The page is taller than it is wide.
+    #[default]
+    Portrait,
+    /// The page is flipped so that it is wider than it is tall.
+    Landscape,
+}
+
+/// Page setup applied to paged exports.
+///
+/// These settings are injected as a `#set page(..)` rule ahead of the document,
+/// so any `#set page` in the document itself still takes precedence. Fields left
+/// as `None` keep Typst's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PageOptions {
+    /// A named paper size understood by Typst, such as `"a4"` or `"us-letter"`.
+    pub paper: Option<String>,
+    /// The page width, overriding the width of `paper`.
+    pub width: Option<Abs>,
+    /// The page height, overriding the height of `paper`.
+    pub height: Option<Abs>,
+    /// The margin applied to all four sides of the page.
+    pub margin: Option<Abs>,
+    /// The page orientation.
+    pub orientation: Option<Orientation>,
+}
+
+impl PageOptions {
+    /// Combines two page setups, preferring the fields set in `other`.
+    fn merge(self, other: Self) -> Self {
+        Self {
+            paper: other.paper.or(self.paper),
+            width: other.width.or(self.width),
+            height: other.height.or(self.height),
+            margin: other.margin.or(self.margin),
+            orientation: other.orientation.or(self.orientation),
+        }
+    }
+
+    /// Renders the options as a Typst `#set page(..)` rule, or an empty string if
+    /// nothing was configured.
+    fn preamble(&self) -> String {
+        let mut args = Vec::new();
+        if let Some(paper) = &self.paper {
+            args.push(format!("paper: {}", paper.as_str().repr()));
+        }
+        if let Some(width) = self.width {
+            args.push(format!("width: {}", width.repr()));
+        }
+        if let Some(height) = self.height {
+            args.push(format!("height: {}", height.repr()));
+        }
+        if let Some(margin) = self.margin {
+            args.push(format!("margin: {}", margin.repr()));
+        }
+        if self.orientation == Some(Orientation::Landscape) {
+            args.push("flipped: true".to_string());
+        }
+
+        if args.is_empty() {
+            String::new()
+        } else {
+            format!("#set page({})\n", args.join(", "))
+        }
+    }
+}
+
+/// A responsive wrapper for HTML output.
+///
+/// HTML export is not paginated, so a document's page width never reaches the
+/// browser. This container gives the content a readable measure that still
+/// shrinks on narrow screens, and optionally flows it into CSS columns. The
+/// output is wrapped in a `<div class="typst-container">` whose inline style
+/// reads these CSS custom properties, falling back to the configured values:
+///
+/// - `--typst-max-width`: the widest the content grows.
+/// - `--typst-columns`: the most columns the content is split into.
+/// - `--typst-column-width`: the narrowest a column gets before the browser
+///   drops to fewer columns.
+/// - `--typst-column-gap`: the space between columns, `2em` by default.
+///
+/// Setting a property on the container or any ancestor overrides the fallback,
+/// so media queries in the app's stylesheet can adjust the layout.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ContainerOptions {
+    /// The widest the content grows, as a CSS length such as `"40rem"`.
+    /// `None` lets it fill the available width.
+    pub max_width: Option<String>,
+    /// The most columns the content is split into. `None` keeps one column.
+    pub columns: Option<u32>,
+    /// The narrowest a column gets, as a CSS length. `None` lets the browser
+    /// always use the full column count.
+    pub column_width: Option<String>,
+}
+
+impl ContainerOptions {
+    /// Wraps an HTML fragment in the container.
+    fn wrap(&self, html: &str) -> String {
+        let escape = |value: &str| value.replace('&', "&amp;").replace('"', "&quot;");
+        let max_width = escape(self.max_width.as_deref().unwrap_or("none"));
+        let columns = self.columns.unwrap_or(1);
+        let column_width = escape(self.column_width.as_deref().unwrap_or("auto"));
+        format!(
+            "<div class=\"typst-container\" style=\"box-sizing: border-box; \
+             max-width: var(--typst-max-width, {max_width}); margin-inline: auto; \
+             columns: var(--typst-columns, {columns}) var(--typst-column-width, {column_width}); \
+             column-gap: var(--typst-column-gap, 2em)\">{html}</div>"
+        )
+    }
+}
+
+/// The compilation world that provides all resources to the Typst compiler.
+#[derive(Clone)]
+struct CompileWorld {
+    library: LazyHash<Library>,
+    book: LazyHash<FontBook>,
+    fonts: Vec<Font>,
+    main: Source,
+    preamble: String,
+    postamble: String,
+    /// Byte range of the user's source within `main`.
+    user_range: Range<usize>,
+    files: HashMap<String, Bytes>,
+    packages: HashMap<PackageSpec, HashMap<String, Bytes>>,
+    package_sources: Vec<PackageSource>,
+    time_budget: Option<Duration>,
+    clock: Clock,
+    deadline: Option<Instant>,
+    cancellation: Option<CancellationToken>,
+    /// Why the world started refusing requests, once it has.
+    interrupted: OnceLock<&'static str>,
+    /// Whether references to missing labels are warned about instead of failing.
+    lenient_references: bool,
+    /// The keys of the provided bibliographies, when references are lenient.
+    citation_keys: Vec<String>,
+    /// Whether equations get their source as an `aria-label`.
+    math_alt_text: bool,
+    /// How equations are represented, after falling back from unsupported strategies.
+    math: MathRendering,
+    /// Whether unknown files outside packages read as empty.
+    missing_files_as_empty: bool,
+    /// Whether unknown files outside packages fall back to a similar path.
+    fuzzy_paths: bool,
+    /// Receives progress events while compiling.
+    progress: Option<Arc<ProgressFn>>,
+}
+
+/// A callback receiving [`Progress`] events.
+type ProgressFn = dyn Fn(Progress) + Send + Sync;
+
+/// Builds the show rule that renders references to missing labels as plain text.
+///
+/// References to `citation_keys` are left alone when the document has a
+/// bibliography, since their keys are not labels in the document.
+fn lenient_references_rule(citation_keys: &[String]) -> String {
+    let keys: String = citation_keys
+        .iter()
+        .map(|key| format!("{}, ", key.as_str().repr()))
+        .collect();
+    format!(
+        "#show ref: it => context if query(it.target).len() == 0 and (query(bibliography).len() == 0 \
+         or str(it.target) not in ({keys})) {{ \"@\" + str(it.target) }} else {{ it }}\n"
+    )
+}
+
+/// A show rule that renders equations in HTML as SVG inside an element that
+/// [`CompileWorld::label_math`] can give an `aria-label`.
+const MATH_SVG: &str = "#show math.equation: it => context if target() == \"html\" { \
+html.elem(if it.block { \"div\" } else { \"span\" }, \
+attrs: (role: \"math\", class: \"typst-math\"), html.frame(it)) } else { it }\n";
+
+/// A show rule that renders equations in HTML as an empty element that
+/// [`CompileWorld::label_math`] fills with the equation's source.
+const MATH_SOURCE: &str = "#show math.equation: it => context if target() == \"html\" { \
+html.elem(if it.block { \"div\" } else { \"span\" }, \
+attrs: (role: \"math\", class: \"typst-math\")) } else { it }\n";
+
+/// Records the page fill and text fill in effect at the end of the document,
+/// where top-level set rules still apply, for [`CompileWorld::page_style`] to
+/// read.
+const PAGE_STYLE: &str =
+    "\n#context [#metadata((fill: page.fill, text: text.fill)) <dioxus-typst-page-style>]\n";
+
+impl CompileWorld {
+    /// Creates a new compilation world with the given source and options.
+    fn new(source: &str, options: &CompileOptions) -> Self {
+        let fonts = load_fonts(options);
+        let book = FontBook::from_fonts(&fonts);
+        let root = options.root.as_deref().unwrap_or_default();
+        let main_id = FileId::new(None, VirtualPath::new(format!("{root}/main.typ")));
+
+        let files = options
+            .files
+            .iter()
+            .map(|(path, content)| (path.clone(), Bytes::new(content.clone())))
+            .collect();
+
+        let packages: HashMap<PackageSpec, HashMap<String, Bytes>> = options
+            .packages
+            .iter()
+            .map(
+                |(spec, pkg_files): (&PackageSpec, &HashMap<String, Vec<u8>>)| {
+                    let converted: HashMap<String, Bytes> = pkg_files
+                        .iter()
+                        .map(|(path, content)| (path.clone(), Bytes::new(content.clone())))
+                        .collect();
+                    (spec.clone(), converted)
+                },
+            )
+            .collect();
+
+        // Inputs are baked into the `sys` module, so they need a library of their own.
+        let mut library = if options.inputs.is_empty() {
+            shared_library(&[Feature::Html])
+        } else {
+            let inputs: Dict = options
+                .inputs
+                .iter()
+                .map(|(key, value)| (key.as_str().into(), Value::Str(value.as_str().into())))
+                .collect();
+            CACHE_STATS.library_misses.fetch_add(1, Ordering::Relaxed);
+            Library::builder()
+                .with_features([Feature::Html].into_iter().collect())
+                .with_inputs(inputs)
+                .build()
+        };
+        if !options.fallback_fonts.is_empty() {
+            let families = std::iter::once("libertinus serif")
+                .chain(options.fallback_fonts.iter().map(String::as_str))
+                .map(FontFamily::new)
+                .collect();
+            library.styles.set(TextElem::font, FontList(families));
+        }
+
+        let math_alt_text = options.math_alt_text.unwrap_or_default();
+        let math = match options.math.unwrap_or_default() {
+            MathRendering::Omit if math_alt_text => MathRendering::Svg,
+            rendering => rendering.resolve(),
+        };
+        let lenient_references = options.lenient_references.unwrap_or_default();
+        let citation_keys: Vec<String> = if lenient_references {
+            options
+                .files
+                .iter()
+                .filter_map(|(path, content)| {
+                    parse_bibliography(path, std::str::from_utf8(content).ok()?)?.ok()
+                })
+                .flat_map(|library| library.keys().map(String::from).collect::<Vec<_>>())
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let mut preamble = String::new();
+        if lenient_references {
+            preamble.push_str(&lenient_references_rule(&citation_keys));
+        }
+        preamble.push_str(match math {
+            MathRendering::Omit | MathRendering::MathMl => "",
+            MathRendering::Svg => MATH_SVG,
+            MathRendering::Source => MATH_SOURCE,
+        });
+        preamble.push_str(options.preamble.as_deref().unwrap_or_default());
+
+        let mut world = Self {
+            library: LazyHash::new(library),
+            book: LazyHash::new(book),
+            fonts,
+            main: Source::new(main_id, String::new()),
+            user_range: 0..0,
+            preamble,
+            postamble: options.postamble.clone().unwrap_or_default(),
+            files,
+            packages,
+            package_sources: if options.package_sources.is_empty() {
+                vec![PackageSource::Preloaded]
+            } else {
+                options.package_sources.clone()
+            },
+            time_budget: options.time_budget,
+            deadline: None,
+            cancellation: options.cancellation.clone(),
+            clock: options.clock.unwrap_or_default(),
+            interrupted: OnceLock::new(),
+            lenient_references,
+            citation_keys,
+            math_alt_text,
+            math,
+            missing_files_as_empty: options.missing_files_as_empty.unwrap_or_default(),
+            fuzzy_paths: options.fuzzy_paths.unwrap_or_default(),
+            progress: None,
+        };
+        world.set_main(source);
+        world
+    }
+
+    /// Creates a compilation world for paged export, with the page setup from
+    /// the options applied ahead of the preamble.
+    fn paged(source: &str, options: &CompileOptions) -> Self {
+        let mut world = Self::new("", options);
+        world.preamble.insert_str(0, &options.page.preamble());
+        world.set_main(source);
+        world
+    }
+
+    /// Replaces the main source while keeping fonts, files, and packages.
+    ///
+    /// The source is wrapped in the preamble and postamble, and the time budget
+    /// starts over.
+    fn set_main(&mut self, source: &str) {
+        let (text, user_range) = wrap_source(&self.preamble, source, &self.postamble);
+        self.main = Source::new(self.main.id(), text);
+        self.user_range = user_range;
+        self.deadline = self.time_budget.map(|budget| Instant::now() + budget);
+        self.interrupted = OnceLock::new();
+    }
+
+    /// Replaces the main source with an already parsed one.
+    ///
+    /// The source is used as is, keeping its parse, when there is nothing to
+    /// wrap it in. Otherwise its text is wrapped like [`set_main`](Self::set_main)
+    /// does and parsed again.
+    fn set_main_source(&mut self, source: &Source) {
+        if self.preamble.is_empty() && self.postamble.is_empty() {
+            self.main = source.clone();
+            self.user_range = 0..source.text().len();
+            self.deadline = self.time_budget.map(|budget| Instant::now() + budget);
+            self.interrupted = OnceLock::new();
+        } else {
+            self.main = Source::new(source.id(), String::new());
+            self.set_main(source.text());
+        }
+    }
+
+    /// Reads the page and text colors in effect at the end of the main source.
+    ///
+    /// The colors are recorded by [`PAGE_STYLE`] in a separate compilation of
+    /// a copy of the world, so the document, its queries, and its output are
+    /// left untouched, including in templates applied with `#show: ..`.
+    fn page_style(&self) -> PageStyle {
+        let mut probe = self.clone();
+        probe.postamble.push_str(PAGE_STYLE);
+        probe.set_main(&self.main.text()[self.user_range.clone()]);
+        typst::compile::<HtmlDocument>(&probe)
+            .output
+            .map(|document| page_style(&document.introspector))
+            .unwrap_or_default()
+    }
+
+    /// Fails once the time budget is spent or the compilation was cancelled.
+    fn check_budget(&self) -> FileResult<()> {
+        let reason = if let Some(reason) = self.interrupted.get() {
+            reason
+        } else if self
+            .cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            self.interrupted.get_or_init(|| "compilation was cancelled")
+        } else if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            self.interrupted
+                .get_or_init(|| "compilation exceeded time budget")
+        } else {
+            return Ok(());
+        };
+        Err(FileError::Other(Some((*reason).into())))
+    }
+
+    /// Compiles the main source, reporting errors and interruptions as a
+    /// [`CompileError`].
+    fn compile<D: Document + 'static>(&self) -> Warned<Result<D, CompileError>> {
+        let mut warned = typst::compile::<D>(self);
+        count_compilation();
+        if self.lenient_references
+            && let Ok(document) = &warned.output
+        {
+            warned.warnings.extend(dangling_references(
+                document.introspector(),
+                &self.citation_keys,
+            ));
+        }
+        if std::any::TypeId::of::<D>() == std::any::TypeId::of::<HtmlDocument>() {
+            for warning in warned.warnings.make_mut() {
+                if warning.message.ends_with(" was ignored during HTML export") {
+                    let hint = if warning.message.starts_with("equation ") {
+                        "choose a representation with `CompileOptions::with_math`"
+                    } else {
+                        "wrap it in `html.frame` to keep it as an SVG"
+                    };
+                    warning.hints.push(hint.into());
+                }
+            }
+            warned.warnings.extend(ignored_html_arguments(self));
+        }
+        if let Ok(document) = &warned.output
+            && let Some(document) = (document as &dyn Any).downcast_ref::<PagedDocument>()
+        {
+            warned
+                .warnings
+                .extend(missing_glyph_warnings(self, document));
+        }
+        if (self.math_alt_text || self.math == MathRendering::Source)
+            && let Ok(document) = &mut warned.output
+            && let Some(document) = (document as &mut dyn Any).downcast_mut::<HtmlDocument>()
+        {
+            self.label_math(&mut document.root.children);
+        }
+        let output = match self.interrupted.get() {
+            Some(reason) => Err(CompileError::Typst(reason.to_string())),
+            None => warned.output.map_err(|errors| self.error(&errors)),
+        };
+        Warned {
+            output,
+            warnings: warned.warnings,
+        }
+    }
+
+    /// Gives the element following each equation's start tag, as produced by
+    /// [`MATH_SVG`] or [`MATH_SOURCE`], the equation's source as its
+    /// `aria-label` or, for [`MathRendering::Source`], as its text.
+    fn label_math(&self, nodes: &mut EcoVec<HtmlNode>) {
+        const ARIA_LABEL: HtmlAttr = HtmlAttr::constant("aria-label");
+
+        let mut source = None;
+        for node in nodes.make_mut() {
+            match node {
+                HtmlNode::Tag(Tag::Start(elem, ..))
+                    if elem.to_packed::<EquationElem>().is_some() =>
+                {
+                    let span = elem.span();
+                    source = span.id().and_then(|id| {
+                        let text = self.source(id).ok()?;
+                        let range = self.range(span)?;
+                        let code = text.text().get(range)?.trim_matches('$');
+                        Some((code.split_whitespace().collect::<Vec<_>>().join(" "), span))
+                    });
+                }
+                HtmlNode::Element(element) => {
+                    if let Some((code, span)) = source.take() {
+                        if self.math == MathRendering::Source {
+                            element
+                                .children
+                                .push(HtmlNode::Text(code.as_str().into(), span));
+                        }
+                        if self.math_alt_text {
+                            element.attrs.push(ARIA_LABEL, code);
+                        }
+                    }
+                    self.label_math(&mut element.children);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Converts Typst diagnostics into a [`CompileError`], resolving their spans.
+    fn error(&self, errors: &[SourceDiagnostic]) -> CompileError {
+        CompileError::Diagnostics(errors.iter().map(|e| self.diagnostic(e)).collect())
+    }
+
+    /// Resolves a Typst diagnostic's span, translating ranges in the main file
+    /// so they are relative to the user's source.
+    fn diagnostic(&self, diagnostic: &SourceDiagnostic) -> Diagnostic {
+        let mut resolved = resolve_diagnostic(self, diagnostic);
+        if diagnostic.span.id() == Some(self.main.id()) {
+            let user = &self.user_range;
+            resolved.range = resolved
+                .range
+                .filter(|r| r.start >= user.start && r.end <= user.end)
+                .map(|r| r.start - user.start..r.end - user.start);
+        }
+        resolved
+    }
+
+    /// Reads a file other than the main source from the pre-loaded files or
+    /// packages.
+    fn read(&self, id: FileId) -> FileResult<Bytes> {
+        let path = id.vpath().as_rooted_path().to_string_lossy();
+        if let Some(package) = id.package() {
+            let result = self.get_package_file(package, &path);
+            let counter = match result {
+                Ok(_) => &CACHE_STATS.package_hits,
+                Err(_) => &CACHE_STATS.package_misses,
+            };
+            counter.fetch_add(1, Ordering::Relaxed);
+            return result;
+        }
+
+        let fuzzy = || self.fuzzy_paths.then(|| self.fuzzy_file(&path)).flatten();
+        match self.files.get(path.as_ref()).or_else(fuzzy) {
+            Some(content) => Ok(content.clone()),
+            None if self.missing_files_as_empty => Ok(Bytes::new(Vec::new())),
+            None => Err(FileError::NotFound(id.vpath().as_rooted_path().into())),
+        }
+    }
+
+    /// Finds the only provided file whose path matches `path` ignoring case, or
+    /// else the only one whose file name does.
+    fn fuzzy_file(&self, path: &str) -> Option<&Bytes> {
+        let name = |path: &str| path.rsplit('/').next().unwrap_or_default().to_lowercase();
+        // Files sharing a path ignoring case also share the name, so an
+        // ambiguous path stays ambiguous by name.
+        let unique = |key: &dyn Fn(&str) -> String| {
+            let wanted = key(path);
+            let mut matches = self.files.iter().filter(|(p, _)| key(p) == wanted);
+            match (matches.next(), matches.next()) {
+                (Some((_, content)), None) => Some(content),
+                _ => None,
+            }
+        };
+        unique(&str::to_lowercase).or_else(|| unique(&name))
+    }
+
+    /// Tells the progress callback, if any, that Typst is loading a file.
+    fn report_load(&self, id: FileId) {
+        if let Some(progress) = &self.progress {
+            progress(Progress::Loading(file_path(id)));
+        }
+    }
+
+    /// Reads a package file from the first package source that has the package.
+    ///
+    /// A package directory whose `typst.toml` doesn't match the requested
+    /// package is skipped, so that a stale copy updated in place is never served.
+    fn get_package_file(&self, package: &PackageSpec, path: &str) -> FileResult<Bytes> {
+        let mut mismatch = None;
+        for source in &self.package_sources {
+            match source {
+                PackageSource::Preloaded => {
+                    if let Some(pkg_files) = self.packages.get(package) {
+                        if let Some(content) = pkg_files.get(path) {
+                            return Ok(content.clone());
+                        }
+                        // Only files are stored, so a directory shows up as a prefix.
+                        let dir = format!("{}/", path.trim_end_matches('/'));
+                        return Err(if pkg_files.keys().any(|key| key.starts_with(&dir)) {
+                            FileError::IsDirectory
+                        } else {
+                            FileError::NotFound(path.into())
+                        });
+                    }
+                }
+                PackageSource::Directory(dir) => {
+                    let root = dir
+                        .join(package.namespace.as_str())
+                        .join(package.name.as_str())
+                        .join(package.version.to_string());
+                    if root.is_dir() {
+                        if let Err(reason) = check_package_dir(&root, package) {
+                            mismatch = Some(reason);
+                            continue;
+                        }
+                        let file = root.join(path.trim_start_matches('/'));
+                        if file.is_dir() {
+                            return Err(FileError::IsDirectory);
+                        }
+                        // A symlink may point anywhere, so refuse files that
+                        // resolve outside the package.
+                        if let (Ok(real), Ok(real_root)) =
+                            (file.canonicalize(), root.canonicalize())
+                            && !real.starts_with(&real_root)
+                        {
+                            return Err(FileError::Other(Some(eco_format!(
+                                "{path} links outside package {package}"
+                            ))));
+                        }
+                        return std::fs::read(&file)
+                            .map(Bytes::new)
+                            .map_err(|e| FileError::from_io(e, &file));
+                    }
+                }
+            }
+        }
+
+        Err(FileError::Package(match mismatch {
+            Some(reason) => PackageError::Other(Some(reason)),
+            None => PackageError::NotFound(package.clone()),
+        }))
+    }
+}
+
+/// Checks that the manifest in a package directory describes `spec`.
+fn check_package_dir(root: &std::path::Path, spec: &PackageSpec) -> Result<(), EcoString> {
+    let manifest = root.join("typst.toml");
+    let fail = |reason: &dyn std::fmt::Display| eco_format!("{}: {reason}", manifest.display());
+    let data = std::fs::read(&manifest).map_err(|e| fail(&e))?;
+    let text = std::str::from_utf8(&data).map_err(|_| fail(&"not valid UTF-8"))?;
+    let parsed: PackageManifest = toml::from_str(text).map_err(|e| fail(&e.message()))?;
+    parsed.validate(spec).map_err(|e| fail(&e))
+}
+
+impl World for CompileWorld {
+    fn library(&self) -> &LazyHash<Library> {
+        &self.library
+    }
+
+    fn book(&self) -> &LazyHash<FontBook> {
+        &self.book
+    }
+
+    fn main(&self) -> FileId {
+        self.main.id()
+    }
+
+    fn source(&self, id: FileId) -> FileResult<Source> {
+        self.check_budget()?;
+
+        if id == self.main.id() {
+            return Ok(self.main.clone());
+        }
+
+        self.report_load(id);
+        let text =
+            String::from_utf8(self.read(id)?.to_vec()).map_err(|_| FileError::InvalidUtf8)?;
+        Ok(Source::new(id, text))
+    }
+
+    fn file(&self, id: FileId) -> FileResult<Bytes> {
+        self.check_budget()?;
+        self.report_load(id);
+        self.read(id)
+    }
+
+    fn font(&self, index: usize) -> Option<Font> {
+        self.check_budget().ok()?;
+        self.fonts.get(index).cloned()
+    }
+
+    fn today(&self, offset: Option<i64>) -> Option<Datetime> {
+        let now = match self.clock {
+            Clock::System => chrono::Local::now().fixed_offset(),
+            Clock::Fixed(now) => now,
+            Clock::Disabled => return None,
+        };
+        let now = match offset {
+            Some(hours) => {
+                let offset = chrono::FixedOffset::east_opt((hours * 3600) as i32)?;
+                now.with_timezone(&offset).naive_local()
+            }
+            None => now.naive_local(),
+        };
+        Datetime::from_ymd_hms(
+            now.year(),
+            now.month().try_into().ok()?,
+            now.day().try_into().ok()?,
+            now.hour().try_into().ok()?,
+            now.minute().try_into().ok()?,
+            now.second().try_into().ok()?,
+        )
+    }
+}
+
+/// Warns about every reference whose label does not exist in the document.
+///
+/// Mirrors the check in [`lenient_references_rule`], which kept these references
+/// from failing the compilation.
+fn dangling_references(
+    introspector: &Introspector,
+    citation_keys: &[String],
+) -> Vec<SourceDiagnostic> {
+    let has_bibliography = !introspector
+        .query(&Selector::Elem(BibliographyElem::ELEM, None))
+        .is_empty();
+    introspector
+        .query(&Selector::Elem(RefElem::ELEM, None))
+        .iter()
+        .filter_map(|content| content.to_packed::<RefElem>())
+        .filter(|reference| {
+            let cited = has_bibliography
+                && citation_keys
+                    .iter()
+                    .any(|key| *key == reference.target.resolve().as_str());
+            !cited
+                && introspector
+                    .query(&Selector::Label(reference.target))
+                    .is_empty()
+        })
+        .map(|reference| {
+            SourceDiagnostic::warning(
+                reference.span(),
+                eco_format!(
+                    "label `{}` does not exist in the document",
+                    reference.target.repr()
+                ),
+            )
+            .with_hint("the reference was rendered as plain text")
+        })
+        .collect()
+}
+
+/// Element arguments that Typst's HTML export drops without a warning, because
+/// they only affect the layout of paged output.
+const HTML_IGNORED_ARGUMENTS: &[(&str, &[&str])] = &[
+    (
+        "text",
+        &[
+            "fill", "font", "size", "spacing", "stroke", "style", "tracking", "weight",
+        ],
+    ),
+    (
+        "block",
+        &[
+            "fill", "height", "inset", "outset", "radius", "stroke", "width",
+        ],
+    ),
+    (
+        "box",
+        &[
+            "baseline", "fill", "height", "inset", "outset", "radius", "stroke", "width",
+        ],
+    ),
+    (
+        "par",
+        &[
+            "first-line-indent",
+            "hanging-indent",
+            "justify",
+            "leading",
+            "spacing",
+        ],
+    ),
+    (
+        "table",
+        &[
+            "align",
+            "column-gutter",
+            "fill",
+            "gutter",
+            "inset",
+            "row-gutter",
+            "stroke",
+        ],
+    ),
+    ("list", &["body-indent", "indent", "marker"]),
+    ("enum", &["body-indent", "indent", "numbering"]),
+    ("figure", &["gap"]),
+];
+
+/// Warns about every argument that HTML export drops silently, as listed in
+/// [`HTML_IGNORED_ARGUMENTS`], whether passed in a call or a set rule.
+///
+/// Only the user's source and the local files it includes or imports are
+/// checked, not the preamble and postamble or packages. Arguments inside
+/// `html.frame(..)`, which keeps them as SVG, and inside conditionals on
+/// `target()`, which may only apply to paged export, are skipped.
+fn ignored_html_arguments(world: &CompileWorld) -> Vec<SourceDiagnostic> {
+    fn collect(
+        node: &LinkedNode,
+        user: &Range<usize>,
+        warnings: &mut Vec<SourceDiagnostic>,
+        files: &mut Vec<String>,
+    ) {
+        let range = node.range();
+        if range.end <= user.start || range.start >= user.end {
+            return;
+        }
+        let call = match (node.cast::<ast::FuncCall>(), node.cast::<ast::SetRule>()) {
+            (Some(call), _) => Some((call.callee(), call.args())),
+            (_, Some(rule)) => Some((rule.target(), rule.args())),
+            _ => None,
+        };
+        if let Some((ast::Expr::FieldAccess(access), _)) = call
+            && let ast::Expr::Ident(module) = access.target()
+            && (module.as_str(), access.field().as_str()) == ("html", "frame")
+        {
+            return;
+        }
+        if let Some(conditional) = node.cast::<ast::Conditional>()
+            && calls_target(conditional.condition().to_untyped())
+        {
+            return;
+        }
+        if let Some((ast::Expr::Ident(callee), args)) = call
+            && let Some((_, ignored)) = HTML_IGNORED_ARGUMENTS
+                .iter()
+                .find(|(element, _)| *element == callee.as_str())
+        {
+            for arg in args.items() {
+                if let ast::Arg::Named(named) = arg
+                    && ignored.contains(&named.name().as_str())
+                {
+                    warnings.push(
+                        SourceDiagnostic::warning(
+                            named.span(),
+                            eco_format!(
+                                "`{}` of {} was ignored during HTML export",
+                                named.name().as_str(),
+                                callee.as_str(),
+                            ),
+                        )
+                        .with_hint("style the element with CSS, or wrap it in `html.frame`"),
+                    );
+                }
+            }
+        }
+        let file = match (
+            node.cast::<ast::ModuleImport>(),
+            node.cast::<ast::ModuleInclude>(),
+        ) {
+            (Some(import), _) => Some(import.source()),
+            (_, Some(include)) => Some(include.source()),
+            _ => None,
+        };
+        if let Some(ast::Expr::Str(path)) = file
+            && !path.get().starts_with('@')
+        {
+            files.push(path.get().to_string());
+        }
+        for child in node.children() {
+            collect(&child, user, warnings, files);
+        }
+    }
+
+    fn calls_target(node: &SyntaxNode) -> bool {
+        matches!(
+            node.cast::<ast::FuncCall>().map(|call| call.callee()),
+            Some(ast::Expr::Ident(callee)) if callee.as_str() == "target"
+        ) || node.children().any(calls_target)
+    }
+
+    let mut warnings = Vec::new();
+    let main = world.main.id();
+    let mut seen = vec![main];
+    let mut queue = vec![main];
+    while let Some(id) = queue.pop() {
+        // Read other files directly, so they aren't reported as loaded twice.
+        let file = if id == main {
+            world.main.clone()
+        } else {
+            let Some(text) = world
+                .read(id)
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes.to_vec()).ok())
+            else {
+                continue;
+            };
+            Source::new(id, text)
+        };
+        let user = if id == main {
+            world.user_range.clone()
+        } else {
+            0..file.text().len()
+        };
+        let mut files = Vec::new();
+        collect(
+            &LinkedNode::new(file.root()),
+            &user,
+            &mut warnings,
+            &mut files,
+        );
+        for path in files {
+            let next = id.join(&path);
+            if !seen.contains(&next) {
+                seen.push(next);
+                queue.push(next);
+            }
+        }
+    }
+    warnings
+}
+
+/// Resolves a Typst diagnostic's span against a world.
+fn resolve_diagnostic(world: &dyn World, diagnostic: &SourceDiagnostic) -> Diagnostic {
+    Diagnostic {
+        message: diagnostic.message.to_string(),
+        hints: diagnostic.hints.iter().map(|h| h.to_string()).collect(),
+        file: diagnostic.span.id().map(file_path),
+        range: world.range(diagnostic.span),
+    }
+}
+
+/// Returns a file's path, prefixed with its package if it has one.
+fn file_path(id: FileId) -> String {
+    let path = id.vpath().as_rooted_path().to_string_lossy();
+    match id.package() {
+        Some(package) => format!("{package}{path}"),
+        None => path.into_owned(),
+    }
+}
+
+/// Surrounds the user's source with a preamble and postamble, keeping each of
+/// them on lines of their own.
+///
+/// Returns the combined text and the byte range the user's source occupies.
+fn wrap_source(preamble: &str, source: &str, postamble: &str) -> (String, Range<usize>) {
+    let mut text = String::with_capacity(preamble.len() + source.len() + postamble.len() + 2);
+    text.push_str(preamble);
+    if !preamble.is_empty() && !preamble.ends_with('\n') {
+        text.push('\n');
+    }
+    let start = text.len();
+    text.push_str(source);
+    let user_range = start..text.len();
+    if !postamble.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text.push_str(postamble);
+    (text, user_range)
+}
+
+/// Loads all available fonts.
+///
+/// User-provided fonts come first, followed by Typst's bundled fonts when the
+/// `embed-fonts` feature is enabled. Fonts in the fallback list are moved to the
+/// front, in the order they were listed.
+fn load_fonts(options: &CompileOptions) -> Vec<Font> {
+    let fonts = options
+        .fonts
+        .iter()
+        .flat_map(|data| Font::iter(Bytes::new(data.clone())));
+
+    #[cfg(feature = "system-fonts")]
+    let fonts = fonts.chain(
+        options
+            .system_fonts
+            .unwrap_or_default()
+            .then(system_fonts)
+            .into_iter()
+            .flatten()
+            .cloned(),
+    );
+
+    #[cfg(feature = "embed-fonts")]
+    let fonts = fonts.chain(typst_assets::fonts().flat_map(|data| Font::iter(Bytes::new(data))));
+
+    let mut fonts: Vec<Font> = fonts.collect();
+
+    // Stable, so fonts outside the fallback list keep their load order.
+    fonts.sort_by_key(|font| {
+        options
+            .fallback_fonts
+            .iter()
+            .position(|family| family.eq_ignore_ascii_case(&font.info().family))
+            .unwrap_or(usize::MAX)
+    });
+    fonts
+}
+
+/// Returns the standard library with the given features enabled.
+///
+/// Each feature set is built once and cloned afterwards. A `Library` is never
+/// mutated by compilation and its modules and styles are reference counted, so
+/// clones are cheap and per-compile changes such as fallback fonts only touch
+/// the clone.
+fn shared_library(features: &[Feature]) -> Library {
+    static LIBRARIES: LazyLock<Mutex<HashMap<Vec<Feature>, Library>>> =
+        LazyLock::new(Default::default);
+    let mut libraries = LIBRARIES.lock().unwrap_or_else(|e| e.into_inner());
+    let counter = if libraries.contains_key(features) {
+        &CACHE_STATS.library_hits
+    } else {
+        &CACHE_STATS.library_misses
+    };
+    counter.fetch_add(1, Ordering::Relaxed);
+    libraries
+        .entry(features.to_vec())
+        .or_insert_with(|| {
+            Library::builder()
+                .with_features(features.iter().copied().collect())
+                .build()
+        })
+        .clone()
+}
+
+/// Collects the paths of all files below `dir`, skipping entries that can't be read.
+///
+/// Symlinks are followed only while their targets stay inside `dir`, and each
+/// directory is read once, so a link out of the tree or back up it is skipped
+/// rather than escaping or recursing forever.
+#[cfg(all(feature = "fonts-dir", not(target_arch = "wasm32")))]
+fn read_dir_recursive(dir: &std::path::Path, files: &mut Vec<PathBuf>) {
+    let Ok(base) = dir.canonicalize() else {
+        return;
+    };
+    let mut visited = std::collections::HashSet::new();
+    collect_files(&base, dir, &mut visited, files);
+}
+
+/// Walks one directory for [`read_dir_recursive`].
+#[cfg(all(feature = "fonts-dir", not(target_arch = "wasm32")))]
+fn collect_files(
+    base: &std::path::Path,
+    dir: &std::path::Path,
+    visited: &mut std::collections::HashSet<PathBuf>,
+    files: &mut Vec<PathBuf>,
+) {
+    let Ok(real) = dir.canonicalize() else {
+        return;
+    };
+    if !real.starts_with(base) || !visited.insert(real) {
+        return;
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let kind = match entry.file_type() {
+            Ok(kind) if kind.is_symlink() => match path.canonicalize() {
+                Ok(target) if target.starts_with(base) => {
+                    std::fs::metadata(&target).map(|metadata| metadata.file_type())
+                }
+                _ => continue,
+            },
+            kind => kind,
+        };
+        // Sockets, pipes, and the like are skipped.
+        match kind {
+            Ok(kind) if kind.is_dir() => collect_files(base, &path, visited, files),
+            Ok(kind) if kind.is_file() => files.push(path),
+            _ => {}
+        }
+    }
+}
+
+/// Fonts installed on the operating system, scanned on first use.
+#[cfg(feature = "system-fonts")]
+fn system_fonts() -> &'static [Font] {
+    static FONTS: OnceLock<Vec<Font>> = OnceLock::new();
+    FONTS.get_or_init(|| {
+        let mut database = fontdb::Database::new();
+        database.load_system_fonts();
+        // Collections hold several faces; read each file only once.
+        let mut files: HashMap<&std::path::Path, Option<Bytes>> = HashMap::new();
+        database
+            .faces()
+            .filter_map(|face| {
+                let fontdb::Source::File(path) = &face.source else {
+                    return None;
+                };
+                let data = files
+                    .entry(path)
+                    .or_insert_with(|| std::fs::read(path).ok().map(Bytes::new))
+                    .clone()?;
+                Font::new(data, face.index)
+            })
+            .collect()
+    })
+}
+
+/// Wraps a stylesheet in a `<style>` block whose rules only apply inside elements
+/// carrying all of the given classes.
+///
+/// The rules are nested inside the class selector, which relies on CSS nesting.
+/// Returns `None` when there is no class to scope the rules to.
+fn scoped_style(class: &str, css: &str) -> Option<String> {
+    let selector: String = class
+        .split_whitespace()
+        .map(|c| format!(".{}", css_escape(c)))
+        .collect();
+    if selector.is_empty() {
+        return None;
+    }
+    Some(style_element(&format!("{selector} {{ {css} }}")))
+}
+
+/// Wraps a stylesheet in a `<style>` element.
+fn style_element(css: &str) -> String {
+    // A literal `</style` would end the block early and leak the rest into the page.
+    format!("<style>{}</style>", css.replace("</style", "<\\/style"))
+}
+
+/// Escapes a CSS identifier the way the browser's `CSS.escape()` does, so a
+/// class name can be used in a selector whatever characters it contains.
+fn css_escape(ident: &str) -> String {
+    let mut escaped = String::with_capacity(ident.len());
+    for (i, c) in ident.chars().enumerate() {
+        let leading_digit = c.is_ascii_digit() && (i == 0 || (i == 1 && ident.starts_with('-')));
+        match c {
+            '\0' => escaped.push('\u{FFFD}'),
+            '\u{1}'..='\u{1F}' | '\u{7F}' => escaped.push_str(&format!("\\{:x} ", u32::from(c))),
+            _ if leading_digit => escaped.push_str(&format!("\\{:x} ", u32::from(c))),
+            '-' if ident == "-" => escaped.push_str("\\-"),
+            '-' | '_' | 'a'..='z' | 'A'..='Z' | '0'..='9' | '\u{80}'.. => escaped.push(c),
+            _ => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+        }
+    }
+    escaped
+}
+
+/// Compiles Typst source to an HTML fragment.
+///
+/// This is the same compilation the [`Typst`] component performs, for use
+/// outside of a component or when the HTML should be stored or sent elsewhere.
+/// Only the contents of the document's `<body>` are returned; use
+/// [`compile_full_html`] for a standalone page.
+///
+/// Images referenced by the document are embedded as base64 `data:` URIs, so the
+/// output is self-contained and needs no asset serving:
+///
+/// ```rust
+/// use dioxus_typst::{compile_html, CompileOptions};
+///
+/// let svg = br#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10"/>"#;
+/// let options = CompileOptions::new().with_file("logo.svg", svg.to_vec());
+/// let html = compile_html(r#"#image("/logo.svg")"#, &options).unwrap();
+/// # assert!(html.starts_with(r#"<img src="data:image/svg+xml;base64,"#));
+/// ```
+///
+/// Nothing is rasterized along the way, so there is no resolution to tune.
+/// Images are embedded with their original bytes, and content Typst has to lay
+/// out itself, such as `html.frame(..)`, becomes inline SVG that stays sharp at
+/// any zoom level:
+///
+/// ```rust
+/// use dioxus_typst::{compile_html, CompileOptions};
+///
+/// let html = compile_html("#html.frame(circle(radius: 1cm))", &CompileOptions::new()).unwrap();
+/// # assert!(html.contains("<svg"));
+/// # assert!(!html.contains("image/png"));
+/// ```
+///
+/// Blank source, such as a new document in a live editor, returns empty HTML
+/// right away without loading fonts or starting the compiler, unless a
+/// preamble or postamble could add content of its own:
+///
+/// ```rust
+/// use dioxus_typst::{compile_html, CompileOptions};
+///
+/// assert_eq!(compile_html("  \n", &CompileOptions::new()).unwrap(), "");
+/// ```
+///
+/// Code blocks with a language keep Typst's own highlighting as inline
+/// styles, and also get a `language-*` class for browser-side highlighters
+/// such as Prism or highlight.js:
+///
+/// ```rust
+/// use dioxus_typst::{compile_html, CompileOptions};
+///
+/// let html = compile_html("```rust\nlet x = 1;\n```", &CompileOptions::new()).unwrap();
+/// # assert!(html.starts_with(r#"<pre><code data-lang="rust" class="language-rust">"#));
+/// # let html = compile_html("Run `ls` or ```sh ls -a```.", &CompileOptions::new()).unwrap();
+/// # assert!(html.contains("<code>ls</code>"));
+/// # assert!(html.contains(r#"<code data-lang="sh" class="language-sh">"#));
+/// ```
+///
+/// A document whose language is written right to left, such as Arabic or
+/// Hebrew, marks its top-level elements with `dir="rtl"` so browsers align and
+/// order the text correctly. The fonts of the `embed-fonts` feature have no
+/// Arabic or Hebrew glyphs; add one with [`CompileOptions::with_font`], and
+/// use [`language_warnings`] to find text no loaded font covers.
+///
+/// ```rust
+/// use dioxus_typst::{compile_html, CompileOptions};
+///
+/// let source = "#set text(lang: \"ar\")\n= مقدمة\n\nمرحبا بالعالم";
+/// let html = compile_html(source, &CompileOptions::new()).unwrap();
+/// # assert_eq!(html, "<h2 dir=\"rtl\">مقدمة</h2>\n    <p dir=\"rtl\">مرحبا بالعالم</p>");
+/// # let html = compile_html("#set text(lang: \"en\")\nHello", &CompileOptions::new()).unwrap();
+/// # assert_eq!(html, "<p>Hello</p>");
+/// ```
+pub fn compile_html(source: &str, options: &CompileOptions) -> Result<String, CompileError> {
+    if source.trim().is_empty() && options.preamble.is_none() && options.postamble.is_none() {
+        return Ok(finish_html(String::new(), options));
+    }
+    let world = CompileWorld::new(source, options);
+    compile_world(&world).map(|html| finish_html(html, options))
+}
+
+/// Reads the colors recorded by [`PAGE_STYLE`].
+fn page_style(introspector: &Introspector) -> PageStyle {
+    let colors = introspector
+        .query(&Selector::Elem(MetadataElem::ELEM, None))
+        .iter()
+        .rev()
+        .find(|content| {
+            content
+                .label()
+                .is_some_and(|label| label.resolve().as_str() == "dioxus-typst-page-style")
+        })
+        .and_then(
+            |content| match &content.to_packed::<MetadataElem>()?.value {
+                Value::Dict(colors) => Some(colors.clone()),
+                _ => None,
+            },
+        );
+    let color = |key: &str| match colors.as_ref()?.get(key) {
+        Ok(Value::Color(color)) => Some(*color),
+        _ => None,
+    };
+    PageStyle {
+        fill: color("fill").map(|fill| fill.to_hex().to_string()),
+        text: color("text")
+            .filter(|&text| text != Color::BLACK)
+            .map(|text| text.to_hex().to_string()),
+    }
+}
+
+/// Compiles Typst source to an HTML fragment together with its page colors,
+/// for the [`Typst`] component.
+fn compile_html_styled(
+    source: &str,
+    options: &CompileOptions,
+) -> Result<(String, PageStyle), CompileError> {
+    if source.trim().is_empty() && options.preamble.is_none() && options.postamble.is_none() {
+        return Ok((finish_html(String::new(), options), PageStyle::default()));
+    }
+    let world = CompileWorld::new(source, options);
+    let document = world.compile::<HtmlDocument>().output?;
+    let page_style = world.page_style();
+    let html = encode_html(&document).map_err(|errors| world.error(&errors))?;
+    Ok((
+        finish_html(body_fragment(&html).to_string(), options),
+        page_style,
+    ))
+}
+
+/// Compiles Typst source to a complete, standalone HTML document.
+///
+/// The result starts with `<!DOCTYPE html>`, declares UTF-8, and takes its
+/// `<title>` from the document's `#set document(title: ..)`, so it can be saved
+/// to a file or sent by email and still open correctly.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::{compile_full_html, CompileOptions};
+///
+/// let source = r#"
+/// #set document(title: "Quarterly Report")
+/// = Summary
+/// "#;
+/// let html = compile_full_html(source, &CompileOptions::new()).unwrap();
+/// # assert!(html.starts_with("<!DOCTYPE html>"));
+/// # assert!(html.contains(r#"<meta charset="utf-8">"#));
+/// # assert!(html.contains("<title>Quarterly Report</title>"));
+/// let path = std::env::temp_dir().join("report.html");
+/// # let path = path.with_file_name(format!("report-{}.html", std::process::id()));
+/// std::fs::write(&path, html).unwrap();
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn compile_full_html(source: &str, options: &CompileOptions) -> Result<String, CompileError> {
+    let world = CompileWorld::new(source, options);
+    let mut html = compile_world_standalone(&world)?;
+    if options.inline_fonts == Some(true)
+        && let Some(head_end) = html.find("</head>")
+    {
+        let style = font_face_style(&used_fonts(source, options)?);
+        html.insert_str(head_end, &style);
+    }
+    Ok(finish_html(html, options))
+}
+
+/// Builds a `<style>` block embedding the given fonts as `@font-face` rules and
+/// setting the body in their families.
+fn font_face_style(fonts: &[Font]) -> String {
+    use base64::Engine;
+
+    let quote = |family: &str| format!("\"{}\"", family.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut css = String::new();
+    let mut families = Vec::new();
+    for font in fonts {
+        let info = font.info();
+        let (mime, format) = match font.data().get(..4) {
+            Some(b"OTTO") => ("font/otf", "opentype"),
+            Some(b"ttcf") => ("font/collection", "collection"),
+            _ => ("font/ttf", "truetype"),
+        };
+        let style = match info.variant.style {
+            FontStyle::Normal => "normal",
+            FontStyle::Italic => "italic",
+            FontStyle::Oblique => "oblique",
+        };
+        css.push_str(&format!(
+            "@font-face {{ font-family: {}; src: url(\"data:{mime};base64,{}\") format(\"{format}\"); \
+             font-weight: {}; font-style: {style}; }}\n",
+            quote(&info.family),
+            base64::engine::general_purpose::STANDARD.encode(font.data()),
+            info.variant.weight.to_number(),
+        ));
+        let family = quote(&info.family);
+        if !families.contains(&family) {
+            families.push(family);
+        }
+    }
+    if !families.is_empty() {
+        css.push_str(&format!("body {{ font-family: {}; }}", families.join(", ")));
+    }
+    format!("{}\n", style_element(&css))
+}
+
+/// Compiles a single math expression to inline HTML.
+///
+/// The expression is written as between `$` signs, which may be included or
+/// left out, and is compiled as an inline equation on its own. Only the
+/// equation's element is returned, without the paragraph around it, ready to
+/// place in running text. Equations render as SVG unless
+/// [`CompileOptions::with_math`] chooses another representation, and a
+/// container from the options is left out.
+///
+/// # Example
+///
+/// ```rust
+/// # if !cfg!(feature = "embed-fonts") { return; }
+/// use dioxus_typst::{compile_math, CompileOptions, MathRendering};
+///
+/// let html = compile_math("a^2 + b^2 = c^2", &CompileOptions::new()).unwrap();
+/// # assert!(html.starts_with(r#"<span role="math" class="typst-math"><svg"#), "{html}");
+/// # assert!(html.ends_with("</svg></span>"));
+/// # let source = CompileOptions::new().with_math(MathRendering::Source);
+/// # assert_eq!(
+/// #     compile_math("$x_1$", &source).unwrap(),
+/// #     r#"<span role="math" class="typst-math">x_1</span>"#,
+/// # );
+/// # assert!(compile_math("sqrt(", &CompileOptions::new()).is_err());
+/// ```
+pub fn compile_math(expr: &str, options: &CompileOptions) -> Result<String, CompileError> {
+    let expr = expr.trim();
+    let expr = expr
+        .strip_prefix('$')
+        .and_then(|e| e.strip_suffix('$'))
+        .unwrap_or(expr)
+        .trim();
+    let options = CompileOptions {
+        math: Some(match options.math.unwrap_or_default() {
+            MathRendering::Omit => MathRendering::Svg,
+            rendering => rendering,
+        }),
+        container: None,
+        ..options.clone()
+    };
+    let html = compile_html(&format!("${expr}$"), &options)?;
+    let inline = html
+        .strip_prefix("<p>")
+        .and_then(|html| html.strip_suffix("</p>"))
+        .unwrap_or(&html);
+    Ok(inline.to_string())
+}
+
+/// Compiles the main source of a custom [`World`] to an HTML fragment.
+///
+/// [`CompileOptions`] covers the common cases, but some setups need behavior it
+/// can't express, such as a virtual clock, fonts from elsewhere, or unusual
+/// package resolution. Implement [`typst::World`] yourself and pass it here to
+/// still get this crate's HTML export and error reporting. Render the result
+/// with `dangerous_inner_html`, as the [`Typst`] component does.
+///
+/// The world must enable [`typst::Feature::Html`] in its library.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::compile_with_world;
+/// use dioxus_typst::typst::{
+///     Feature, Library, LibraryExt, World,
+///     diag::{FileError, FileResult},
+///     foundations::{Bytes, Datetime},
+///     syntax::{FileId, Source, VirtualPath},
+///     text::{Font, FontBook},
+///     utils::LazyHash,
+/// };
+///
+/// struct SnippetWorld {
+///     library: LazyHash<Library>,
+///     book: LazyHash<FontBook>,
+///     main: Source,
+/// }
+///
+/// impl World for SnippetWorld {
+///     fn library(&self) -> &LazyHash<Library> { &self.library }
+///     fn book(&self) -> &LazyHash<FontBook> { &self.book }
+///     fn main(&self) -> FileId { self.main.id() }
+///     fn source(&self, id: FileId) -> FileResult<Source> {
+///         if id == self.main.id() {
+///             Ok(self.main.clone())
+///         } else {
+///             Err(FileError::NotFound(id.vpath().as_rooted_path().into()))
+///         }
+///     }
+///     fn file(&self, id: FileId) -> FileResult<Bytes> {
+///         Err(FileError::NotFound(id.vpath().as_rooted_path().into()))
+///     }
+///     fn font(&self, _: usize) -> Option<Font> { None }
+///     fn today(&self, _: Option<i64>) -> Option<Datetime> {
+///         Datetime::from_ymd(2000, 1, 1)
+///     }
+/// }
+///
+/// let world = SnippetWorld {
+///     library: LazyHash::new(
+///         Library::builder().with_features([Feature::Html].into_iter().collect()).build(),
+///     ),
+///     book: LazyHash::new(FontBook::new()),
+///     main: Source::new(
+///         FileId::new(None, VirtualPath::new("/main.typ")),
+///         "Printed on #datetime.today().display()".to_string(),
+///     ),
+/// };
+/// let html = compile_with_world(&world).unwrap();
+/// # assert_eq!(html, "<p>Printed on 2000-01-01</p>");
+/// ```
+pub fn compile_with_world(world: &dyn World) -> Result<String, CompileError> {
+    let error = |errors: &[SourceDiagnostic]| {
+        CompileError::Diagnostics(
+            errors
+                .iter()
+                .map(|e| resolve_diagnostic(world, e))
+                .collect(),
+        )
+    };
+    let warned = typst::compile::<HtmlDocument>(world);
+    count_compilation();
+    let document = warned.output.map_err(|errors| error(&errors))?;
+    let html = encode_html(&document).map_err(|errors| error(&errors))?;
+    Ok(body_fragment(&html).to_string())
+}
+
+/// A stage of compilation, reported by [`compile_html_with_progress`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Progress {
+    /// Typst started parsing, evaluating, and laying out the document.
+    Compiling,
+    /// Typst is reading a file or package file, given by its path. The main
+    /// document is not reported, and files Typst has already read during an
+    /// earlier compilation may not be reported again.
+    Loading(String),
+    /// The document is laid out and is being serialized to HTML.
+    Exporting,
+    /// The HTML is ready.
+    Finished,
+}
+
+/// Compiles Typst source to an HTML fragment, reporting progress along the way.
+///
+/// Typst itself doesn't report how far through a layout it is, so the events
+/// are coarse: the start of compilation, each file it loads, the start of
+/// HTML export, and the end. That is still enough to replace a frozen spinner
+/// with a status line. The callback runs on the compiling thread, so keep it
+/// cheap, for example by sending the event over a channel. Events stop at the
+/// first error.
+///
+/// # Example
+///
+/// ```rust
+/// use std::sync::mpsc;
+/// use dioxus_typst::{compile_html_with_progress, CompileOptions, Progress};
+///
+/// let options = CompileOptions::new().with_file("chapter.typ", b"= Chapter".to_vec());
+/// let (sender, receiver) = mpsc::channel();
+/// let html = compile_html_with_progress(r#"#include "chapter.typ""#, &options, move |event| {
+///     let _ = sender.send(event);
+/// })
+/// .unwrap();
+/// let events: Vec<Progress> = receiver.iter().collect();
+/// # assert_eq!(html, "<h2>Chapter</h2>");
+/// # assert_eq!(
+/// #     events,
+/// #     [
+/// #         Progress::Compiling,
+/// #         Progress::Loading("/chapter.typ".to_string()),
+/// #         Progress::Exporting,
+/// #         Progress::Finished,
+/// #     ],
+/// # );
+/// ```
+pub fn compile_html_with_progress(
+    source: &str,
+    options: &CompileOptions,
+    on_progress: impl Fn(Progress) + Send + Sync + 'static,
+) -> Result<String, CompileError> {
+    let mut world = CompileWorld::new(source, options);
+    let progress: Arc<ProgressFn> = Arc::new(on_progress);
+    world.progress = Some(progress.clone());
+
+    progress(Progress::Compiling);
+    let document = world.compile::<HtmlDocument>().output?;
+    progress(Progress::Exporting);
+    let html = encode_html(&document).map_err(|errors| world.error(&errors))?;
+    let html = finish_html(body_fragment(&html).to_string(), options);
+    progress(Progress::Finished);
+    Ok(html)
+}
+
+/// Compiles the main source of an already constructed world to an HTML fragment.
+fn compile_world(world: &CompileWorld) -> Result<String, CompileError> {
+    compile_world_standalone(world).map(|html| body_fragment(&html).to_string())
+}
+
+/// Compiles the main source of an already constructed world to a full HTML
+/// document.
+fn compile_world_standalone(world: &CompileWorld) -> Result<String, CompileError> {
+    let document = world.compile::<HtmlDocument>().output?;
+    encode_html(&document).map_err(|errors| world.error(&errors))
+}
+
+/// Serializes a compiled document, marking the direction of right-to-left
+/// documents and the language of code blocks.
+///
+/// Typst lays out Arabic, Hebrew, and other right-to-left text correctly, but
+/// its HTML export doesn't say so, and browsers default to left-to-right.
+/// When the document's language is right-to-left, `dir="rtl"` is added to the
+/// `<html>` element and to every top-level element of the body, so fragments
+/// keep their direction after the body is stripped.
+///
+/// Typst marks the language of raw text only with `data-lang`, while
+/// highlighters such as Prism and highlight.js look for a `language-*` class,
+/// so every `<code data-lang="..">` without a class gets one as well.
+fn encode_html(document: &HtmlDocument) -> SourceResult<String> {
+    const DIR: HtmlAttr = HtmlAttr::constant("dir");
+
+    let rtl =
+        matches!(document.info.locale, Smart::Custom(locale) if locale.lang.dir() == Dir::RTL);
+    let mark = |element: &mut HtmlElement| {
+        if element.attrs.get(DIR).is_none() {
+            element.attrs.push(DIR, "rtl");
+        }
+    };
+    let mut document = document.clone();
+    if rtl {
+        mark(&mut document.root);
+    }
+    for node in document.root.children.make_mut() {
+        if let HtmlNode::Element(body) = node
+            && body.tag.resolve().as_str() == "body"
+        {
+            if rtl {
+                for child in body.children.make_mut() {
+                    if let HtmlNode::Element(element) = child {
+                        mark(element);
+                    }
+                }
+            }
+            mark_code_languages(&mut body.children);
+        }
+    }
+    typst_html::html(&document)
+}
+
+/// Adds a `language-*` class to each `<code>` element that has a `data-lang`.
+fn mark_code_languages(nodes: &mut EcoVec<HtmlNode>) {
+    const CLASS: HtmlAttr = HtmlAttr::constant("class");
+    const DATA_LANG: HtmlAttr = HtmlAttr::constant("data-lang");
+
+    // Only copy the parts of the tree that contain code.
+    fn has_code(nodes: &[HtmlNode]) -> bool {
+        nodes.iter().any(|node| match node {
+            HtmlNode::Element(element) => {
+                element.tag.resolve().as_str() == "code" || has_code(&element.children)
+            }
+            _ => false,
+        })
+    }
+
+    if !has_code(nodes) {
+        return;
+    }
+    for node in nodes.make_mut() {
+        let HtmlNode::Element(element) = node else {
+            continue;
+        };
+        if element.tag.resolve().as_str() == "code"
+            && element.attrs.get(CLASS).is_none()
+            && let Some(lang) = element.attrs.get(DATA_LANG).cloned()
+        {
+            element.attrs.push(CLASS, eco_format!("language-{lang}"));
+        } else {
+            mark_code_languages(&mut element.children);
+        }
+    }
+}
+
+/// Applies the output options to compiled HTML.
+fn finish_html(html: String, options: &CompileOptions) -> String {
+    let pretty = options.format == Some(HtmlFormat::Pretty);
+    let html = match &options.container {
+        Some(container) => {
+            // Standalone documents keep their head; only the body is wrapped.
+            let body = body_fragment(&html);
+            let start = body.as_ptr() as usize - html.as_ptr() as usize;
+            let end = start + body.len();
+            let wrapped = if pretty {
+                let line_start = html[..start].rfind('\n').map_or(0, |i| i + 1);
+                let base = &html[line_start..start];
+                let inner = reindent(body, &format!("{base}  "));
+                container.wrap(&format!("\n{inner}\n{base}"))
+            } else {
+                container.wrap(body)
+            };
+            format!("{}{wrapped}{}", &html[..start], &html[end..])
+        }
+        None if pretty && !html.starts_with("<!DOCTYPE") => reindent(&html, ""),
+        None => html,
+    };
+    match options.format.unwrap_or_default() {
+        HtmlFormat::Minified => minify_html(&html),
+        HtmlFormat::Compact | HtmlFormat::Pretty => html,
+    }
+}
+
+/// Shifts a fragment whose first line was trimmed, as [`body_fragment`]
+/// returns it, so that its outermost elements start at `indent`.
+///
+/// The encoder writes text line breaks as `<br>`, so every line break in the
+/// fragment is indentation between elements and can be changed safely.
+fn reindent(fragment: &str, indent: &str) -> String {
+    let mut lines = fragment.split('\n');
+    let first = lines.next().unwrap_or_default();
+    let rest: Vec<&str> = lines.collect();
+    let margin = rest
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start_matches(' ').len())
+        .min()
+        .unwrap_or(0);
+    let mut out = format!("{indent}{first}");
+    for line in rest {
+        out.push('\n');
+        if !line.trim().is_empty() {
+            out.push_str(indent);
+            out.push_str(&line[margin.min(line.len())..]);
+        }
+    }
+    out
+}
+
+/// Drops comments and insignificant whitespace from HTML.
+///
+/// Whitespace runs that span a line break between two tags are indentation and
+/// are removed. Any other run collapses to a single space, since it may separate
+/// inline content. Raw text elements are copied verbatim.
+fn minify_html(html: &str) -> String {
+    const VERBATIM: [&str; 4] = ["pre", "textarea", "script", "style"];
+
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(c) = rest.chars().next() {
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+        } else if c == '<' {
+            let end = tag_end(rest);
+            let tag = &rest[..end];
+            out.push_str(tag);
+            rest = &rest[end..];
+
+            let name = tag[1..]
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .next()
+                .unwrap_or_default();
+            if let Some(verbatim) = VERBATIM.iter().find(|v| v.eq_ignore_ascii_case(name)) {
+                let close = rest.find(&format!("</{verbatim}")).unwrap_or(rest.len());
+                out.push_str(&rest[..close]);
+                rest = &rest[close..];
+            }
+        } else if c.is_ascii_whitespace() {
+            let trimmed = rest.trim_start_matches(|c: char| c.is_ascii_whitespace());
+            let run = &rest[..rest.len() - trimmed.len()];
+            rest = trimmed;
+            let indentation = run.contains('\n')
+                && (out.is_empty() || out.ends_with('>'))
+                && (rest.is_empty() || rest.starts_with('<'));
+            if !indentation {
+                out.push(' ');
+            }
+        } else {
+            let end = rest
+                .find(|c: char| c == '<' || c.is_ascii_whitespace())
+                .unwrap_or(rest.len());
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+        }
+    }
+    out
+}
+
+/// Returns the byte length of the tag at the start of `html`, skipping over
+/// quoted attribute values.
+fn tag_end(html: &str) -> usize {
+    let mut quote = None;
+    for (i, c) in html.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '>') => return i + 1,
+            _ => {}
+        }
+    }
+    html.len()
+}
+
+/// Returns the contents of the `<body>` element of a serialized HTML document,
+/// or the whole input if it has no body.
+fn body_fragment(html: &str) -> &str {
+    let Some(open) = html.find("<body") else {
+        return html;
+    };
+    let Some(start) = html[open..].find('>').map(|end| open + end + 1) else {
+        return html;
+    };
+    let end = html
+        .rfind("</body>")
+        .filter(|&end| end >= start)
+        .unwrap_or(html.len());
+    html[start..end].trim()
+}
+
+/// Compiles Typst source to HTML, split into one fragment per page.
+///
+/// Typst's HTML export is not paginated: page breaks are dropped while the
+/// document is laid out for HTML, so the exporter never sees page boundaries.
+/// Until it does, this always returns a single fragment containing the whole
+/// document, identical to what the [`Typst`] component renders.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::{compile_pages, CompileOptions};
+///
+/// let source = r#"
+/// First page
+/// #pagebreak()
+/// Second page
+/// "#;
+/// let pages = compile_pages(source, &CompileOptions::new()).unwrap();
+/// # assert_eq!(pages.len(), 1);
+/// # assert!(pages[0].contains("First page"));
+/// # assert!(pages[0].contains("Second page"));
+/// ```
+pub fn compile_pages(source: &str, options: &CompileOptions) -> Result<Vec<String>, CompileError> {
+    compile_html(source, options).map(|html| vec![html])
+}
+
+/// Compiles an already parsed Typst source to an HTML fragment.
+///
+/// Like [`compile_html`], but for a [`Source`] the caller keeps, so one parse
+/// serves several exports of the same document, such as this and
+/// [`compile_source_paged`]. Typst's memoization then also reuses evaluation
+/// between them. The source's [`FileId`] is kept, so relative paths resolve
+/// from its location and diagnostics point into it. A preamble, postamble,
+/// page setup, or an option implemented as a show rule, like
+/// [`CompileOptions::with_lenient_references`], needs the source wrapped, and
+/// the wrapped text is parsed again.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::{compile_source, compile_source_paged, CompileOptions, FileId, Source};
+/// use dioxus_typst::typst::syntax::VirtualPath;
+///
+/// let id = FileId::new(None, VirtualPath::new("/report.typ"));
+/// let source = Source::new(id, "= Report\n#lorem(5)".to_string());
+/// let options = CompileOptions::new();
+/// let html = compile_source(&source, &options).unwrap();
+/// let document = compile_source_paged(&source, &options).unwrap();
+/// # assert!(html.starts_with("<h2>Report</h2>"));
+/// # assert_eq!(document.pages.len(), 1);
+/// # let broken = Source::new(id, "#oops".to_string());
+/// # let error = compile_source(&broken, &options).unwrap_err();
+/// # let dioxus_typst::CompileError::Diagnostics(errors) = error else { panic!() };
+/// # assert_eq!((errors[0].file.as_deref(), errors[0].range.clone()), (Some("/report.typ"), Some(1..5)));
+/// # let wrapped = options.clone().with_preamble("#let intro = [Hi]");
+/// # assert!(compile_source(&Source::detached("#intro"), &wrapped).unwrap().contains("Hi"));
+/// ```
+pub fn compile_source(source: &Source, options: &CompileOptions) -> Result<String, CompileError> {
+    let mut world = CompileWorld::new("", options);
+    world.set_main_source(source);
+    compile_world(&world).map(|html| finish_html(html, options))
+}
+
+/// Compiles an already parsed Typst source to a paged document.
+///
+/// The paged counterpart of [`compile_source`], with the page setup from
+/// [`CompileOptions::page`] applied like [`compile_paged`] does.
+pub fn compile_source_paged(
+    source: &Source,
+    options: &CompileOptions,
+) -> Result<PagedDocument, CompileError> {
+    let mut world = CompileWorld::paged("", options);
+    world.set_main_source(source);
+    world.compile::<PagedDocument>().output
+}
+
+/// Compiles Typst source to a paged document.
+///
+/// The page setup from [`CompileOptions::page`] is applied before the document.
+/// The result can be handed to Typst's PDF, PNG, or SVG exporters, or use
+/// `compile_pdf`, `compile_png`, or `compile_svg` from the features of the same
+/// names to do both at once.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::{compile_paged, Abs, CompileOptions, PageOptions};
+///
+/// let options = CompileOptions::new().with_page(PageOptions {
+///     width: Some(Abs::pt(200.0)),
+///     height: Some(Abs::pt(100.0)),
+///     ..Default::default()
+/// });
+/// let document = compile_paged("#pagebreak()", &options).unwrap();
+/// # assert_eq!(document.pages.len(), 2);
+/// # assert_eq!(document.pages[0].frame.width(), Abs::pt(200.0));
+/// ```
+pub fn compile_paged(
+    source: &str,
+    options: &CompileOptions,
+) -> Result<PagedDocument, CompileError> {
+    let world = CompileWorld::paged(source, options);
+    world.compile::<PagedDocument>().output
+}
+
+/// Compiles Typst source to an HTML document without serializing it.
+///
+/// This stops where [`compile_html`] would hand the document to
+/// `typst_html::html`, so its element tree can be inspected or rewritten first,
+/// which is more reliable than editing the serialized markup. Pass the result
+/// to `typst_html::html` to get a standalone page. The output options
+/// [`CompileOptions::container`] and [`CompileOptions::format`], the
+/// `dir="rtl"` marking of right-to-left documents, and the `language-*`
+/// classes on code only apply to serialized HTML and are left out here.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::{compile_document, CompileOptions};
+/// use dioxus_typst::typst_html::{HtmlElement, HtmlNode};
+///
+/// fn count(element: &HtmlElement, tag: &str) -> usize {
+///     let here = usize::from(element.tag.resolve().as_str() == tag);
+///     here + element
+///         .children
+///         .iter()
+///         .map(|node| match node {
+///             HtmlNode::Element(child) => count(child, tag),
+///             _ => 0,
+///         })
+///         .sum::<usize>()
+/// }
+///
+/// let document = compile_document("= Title\n\nOne.\n\nTwo.", &CompileOptions::new()).unwrap();
+/// let paragraphs = count(&document.root, "p");
+/// # assert_eq!(paragraphs, 2);
+/// # assert!(typst_html::html(&document).unwrap().contains("<h2>Title</h2>"));
+/// ```
+pub fn compile_document(
+    source: &str,
+    options: &CompileOptions,
+) -> Result<HtmlDocument, CompileError> {
+    let world = CompileWorld::new(source, options);
+    world.compile::<HtmlDocument>().output
+}
+
+/// Compiles Typst source to a PDF file.
+///
+/// Requires the `pdf` feature.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::{compile_pdf, CompileOptions};
+///
+/// let pdf = compile_pdf("= Invoice", &CompileOptions::new()).unwrap();
+/// # assert!(pdf.starts_with(b"%PDF-"));
+/// let path = std::env::temp_dir().join("invoice.pdf");
+/// # let path = path.with_file_name(format!("invoice-{}.pdf", std::process::id()));
+/// std::fs::write(&path, pdf).unwrap();
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+#[cfg(feature = "pdf")]
+pub fn compile_pdf(source: &str, options: &CompileOptions) -> Result<Vec<u8>, CompileError> {
+    let world = CompileWorld::paged(source, options);
+    let document = world.compile::<PagedDocument>().output?;
+    typst_pdf::pdf(&document, &typst_pdf::PdfOptions::default())
+        .map_err(|errors| world.error(&errors))
+}
+
+/// Compiles Typst source to one PNG image per page.
+///
+/// `pixels_per_pt` sets the resolution: `1.0` renders at 72 DPI, `2.0` at 144.
+///
+/// Requires the `png` feature.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::{compile_png, CompileOptions};
+///
+/// let pages = compile_png("One #pagebreak() Two", &CompileOptions::new(), 2.0).unwrap();
+/// # assert_eq!(pages.len(), 2);
+/// # assert!(pages[0].starts_with(b"\x89PNG"));
+/// ```
+#[cfg(feature = "png")]
+pub fn compile_png(
+    source: &str,
+    options: &CompileOptions,
+    pixels_per_pt: f32,
+) -> Result<Vec<Vec<u8>>, CompileError> {
+    compile_paged(source, options)?
+        .pages
+        .iter()
+        .map(|page| {
+            typst_render::render(page, pixels_per_pt)
+                .encode_png()
+                .map_err(|e| CompileError::Typst(format!("failed to encode PNG: {e}")))
+        })
+        .collect()
+}
+
+/// Compiles Typst source to one SVG image per page.
+///
+/// Requires the `svg` feature.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::{compile_svg, CompileOptions};
+///
+/// let pages = compile_svg("= Diagram", &CompileOptions::new()).unwrap();
+/// # assert_eq!(pages.len(), 1);
+/// # assert!(pages[0].starts_with("<svg"));
+/// ```
+#[cfg(feature = "svg")]
+pub fn compile_svg(source: &str, options: &CompileOptions) -> Result<Vec<String>, CompileError> {
+    Ok(compile_paged(source, options)?
+        .pages
+        .iter()
+        .map(typst_svg::svg)
+        .collect())
+}
+
+/// The HTML and paged forms of the same document, from [`compile_html_and_paged`].
+#[derive(Debug, Clone)]
+pub struct HtmlAndPaged {
+    /// The HTML fragment, as returned by [`compile_html`].
+    pub html: String,
+    /// The paged document, as returned by [`compile_paged`].
+    pub paged: PagedDocument,
+}
+
+/// Compiles Typst source to both an HTML fragment and a paged document.
+///
+/// Use this to show a document on screen while offering a PDF or PNG download
+/// of it. Both exports are compiled from one world, so they see exactly the same
+/// source, files, packages, and fonts, and Typst reuses the
+/// parsing and evaluation work of the first export for the second. The page
+/// setup from [`CompileOptions::page`] only affects the paged document.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::{compile_html_and_paged, CompileOptions};
+///
+/// let both = compile_html_and_paged("= Invoice", &CompileOptions::new()).unwrap();
+/// # assert!(both.html.contains("Invoice</h2>"));
+/// # assert_eq!(both.paged.pages.len(), 1);
+/// ```
+pub fn compile_html_and_paged(
+    source: &str,
+    options: &CompileOptions,
+) -> Result<HtmlAndPaged, CompileError> {
+    let world = CompileWorld::paged(source, options);
+    let html = compile_world(&world).map(|html| finish_html(html, options))?;
+    let paged = world.compile::<PagedDocument>().output?;
+    Ok(HtmlAndPaged { html, paged })
+}
+
+/// Compiles Typst source and reports the fonts used to lay it out.
+///
+/// The document is compiled to its paged form and every shaped text run is
+/// inspected, so only fonts that actually contributed glyphs are returned, in
+/// order of first use. Each [`Font`] exposes its family through `info().family`,
+/// its face index within a collection through `index()`, and its file through
+/// `data()`, which is enough to bundle just those fonts for web delivery.
+///
+/// # Example
+///
+/// ```rust
+/// # if !cfg!(feature = "embed-fonts") { return; }
+/// use dioxus_typst::{used_fonts, CompileOptions};
+///
+/// let source = r#"
+/// Body text in the default font.
+/// #text(font: "DejaVu Sans Mono")[Monospaced.]
+/// "#;
+/// let fonts = used_fonts(source, &CompileOptions::new()).unwrap();
+/// # let families: Vec<&str> = fonts.iter().map(|f| f.info().family.as_str()).collect();
+/// # assert!(families.contains(&"DejaVu Sans Mono"));
+/// # assert!(!families.contains(&"New Computer Modern"));
+/// ```
+pub fn used_fonts(source: &str, options: &CompileOptions) -> Result<Vec<Font>, CompileError> {
+    fn collect(frame: &Frame, fonts: &mut Vec<Font>) {
+        for (_, item) in frame.items() {
+            match item {
+                FrameItem::Group(group) => collect(&group.frame, fonts),
+                FrameItem::Text(text) if !fonts.contains(&text.font) => {
+                    fonts.push(text.font.clone());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let document = compile_paged(source, options)?;
+    let mut fonts = Vec::new();
+    for page in &document.pages {
+        collect(&page.frame, &mut fonts);
+    }
+    Ok(fonts)
+}
+
+/// A font requested by a document that is not loaded, and the font Typst used
+/// in its place.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FontWarning {
+    /// The requested font family, lowercased as Typst reports it.
+    pub requested: String,
+    /// The family Typst falls back to for regular Latin text, if any font is loaded.
+    pub used: Option<String>,
+}
+
+impl std::fmt::Display for FontWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.used {
+            Some(used) => write!(f, "requested '{}', used '{}'", self.requested, used),
+            None => write!(f, "requested '{}', but no fonts are loaded", self.requested),
+        }
+    }
+}
+
+/// Compiles Typst source and collects font substitutions.
+///
+/// Typst silently substitutes a fallback when a document asks for a font family
+/// that isn't loaded. This surfaces each missing family once, together with the
+/// family used instead, so you know which font to add with
+/// [`CompileOptions::with_font`].
+///
+/// # Example
+///
+/// ```rust
+/// # if !cfg!(feature = "embed-fonts") { return; }
+/// use dioxus_typst::{font_warnings, CompileOptions};
+///
+/// let source = r#"#set text(font: "Times New Roman")
+/// Hello"#;
+/// let warnings = font_warnings(source, &CompileOptions::new()).unwrap();
+/// # assert_eq!(warnings.len(), 1);
+/// # assert_eq!(warnings[0].requested, "times new roman");
+/// # assert_eq!(warnings[0].to_string(), "requested 'times new roman', used 'Libertinus Serif'");
+/// ```
+pub fn font_warnings(
+    source: &str,
+    options: &CompileOptions,
+) -> Result<Vec<FontWarning>, CompileError> {
+    let world = CompileWorld::new(source, options);
+    let warned = world.compile::<HtmlDocument>();
+    warned.output?;
+
+    let used = world
+        .book
+        .select_fallback(None, FontVariant::default(), "a")
+        .and_then(|index| world.book.info(index))
+        .map(|info| info.family.clone());
+
+    let mut warnings: Vec<FontWarning> = Vec::new();
+    for warning in &warned.warnings {
+        if let Some(family) = warning.message.strip_prefix("unknown font family: ")
+            && !warnings.iter().any(|w| w.requested == family)
+        {
+            warnings.push(FontWarning {
+                requested: family.to_string(),
+                used: used.clone(),
+            });
+        }
+    }
+    Ok(warnings)
+}
+
+/// Text in a document language that no loaded font can display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageWarning {
+    /// The language set with `#set text(lang: ..)`, as an ISO 639 code.
+    pub lang: String,
+    /// The characters that render as missing glyphs, in order of appearance.
+    pub missing: Vec<char>,
+}
+
+impl std::fmt::Display for LanguageWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let missing: String = self.missing.iter().collect();
+        write!(
+            f,
+            "no loaded font covers text in language '{}': {}",
+            self.lang, missing
+        )
+    }
+}
+
+/// Compiles Typst source and reports languages whose text the loaded fonts
+/// can't display.
+///
+/// When no font in the font book covers a character, Typst lays it out with
+/// the font's missing glyph, which browsers and PDF viewers show as an empty
+/// box. The fonts of the `embed-fonts` feature only cover Latin, Greek and
+/// Cyrillic scripts, so a document that sets `lang` to Chinese, Japanese,
+/// Arabic and so on needs a matching font added with
+/// [`CompileOptions::with_font`]. Each language is reported once, with every
+/// uncovered character.
+///
+/// # Example
+///
+/// ```rust
+/// # if !cfg!(feature = "embed-fonts") { return; }
+/// use dioxus_typst::{language_warnings, CompileOptions};
+///
+/// let source = r#"#set text(lang: "zh")
+/// 你好"#;
+/// for warning in language_warnings(source, &CompileOptions::new()).unwrap() {
+///     eprintln!("{warning}");
+/// }
+/// # let warnings = language_warnings(source, &CompileOptions::new()).unwrap();
+/// # assert_eq!(warnings.len(), 1);
+/// # assert_eq!(warnings[0].lang, "zh");
+/// # assert_eq!(warnings[0].missing, ['你', '好']);
+/// # assert!(language_warnings("#set text(lang: \"de\")\nHallo", &CompileOptions::new()).unwrap().is_empty());
+/// ```
+pub fn language_warnings(
+    source: &str,
+    options: &CompileOptions,
+) -> Result<Vec<LanguageWarning>, CompileError> {
+    fn collect(frame: &Frame, warnings: &mut Vec<LanguageWarning>) {
+        for (_, item) in frame.items() {
+            match item {
+                FrameItem::Group(group) => collect(&group.frame, warnings),
+                FrameItem::Text(text) => {
+                    let lang = text.lang.as_str();
+                    for glyph in text.glyphs.iter().filter(|glyph| glyph.id == 0) {
+                        let chars = text.text[glyph.range()].chars();
+                        for c in chars.filter(|c| !c.is_whitespace()) {
+                            let index = match warnings.iter().position(|w| w.lang == lang) {
+                                Some(index) => index,
+                                None => {
+                                    warnings.push(LanguageWarning {
+                                        lang: lang.to_string(),
+                                        missing: Vec::new(),
+                                    });
+                                    warnings.len() - 1
+                                }
+                            };
+                            if !warnings[index].missing.contains(&c) {
+                                warnings[index].missing.push(c);
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let document = compile_paged(source, options)?;
+    let mut warnings = Vec::new();
+    for page in &document.pages {
+        collect(&page.frame, &mut warnings);
+    }
+    Ok(warnings)
+}
+
+/// Compiles Typst source and reports every character the chosen fonts can't
+/// display, as warnings pointing at where it is used.
+///
+/// A document that sets `#set text(fallback: false)` asks Typst to use only
+/// the fonts it names, so any character they lack is drawn as a missing glyph
+/// rather than taken from another font. Typst does this silently. This
+/// surfaces one warning per font and character, located at its first use,
+/// naming the font that lacks it, so the fix is clear: add a font that covers
+/// it with [`CompileOptions::with_font`] or allow fallback again. With
+/// fallback enabled, only characters no loaded font covers are reported; see
+/// [`language_warnings`] for those grouped by language.
+///
+/// HTML export never shapes text, so [`compile`] and the [`Typst`] component
+/// don't report these. Finding them lays the document out as pages, so call
+/// this separately, for example when a document is saved rather than on
+/// every render.
+///
+/// # Example
+///
+/// ```rust
+/// # if !cfg!(feature = "embed-fonts") { return; }
+/// use dioxus_typst::{missing_glyphs, CompileOptions};
+///
+/// let source = r#"#set text(font: "DejaVu Sans Mono", fallback: false)
+/// Hello, 世界"#;
+/// for warning in missing_glyphs(source, &CompileOptions::new()).unwrap() {
+///     eprintln!("{}", warning.message);
+/// }
+/// # let warnings = missing_glyphs(source, &CompileOptions::new()).unwrap();
+/// # assert_eq!(warnings.len(), 2);
+/// # assert_eq!(warnings[0].message, "font 'DejaVu Sans Mono' has no glyph for '世' (U+4E16)");
+/// # assert_eq!(&source[warnings[1].range.clone().unwrap()], "界");
+/// # let compiled = dioxus_typst::compile(source, &CompileOptions::new()).unwrap();
+/// # assert!(!compiled.warnings.iter().any(|w| w.message.contains("no glyph")));
+/// # assert!(missing_glyphs("Plain text", &CompileOptions::new()).unwrap().is_empty());
+/// ```
+pub fn missing_glyphs(
+    source: &str,
+    options: &CompileOptions,
+) -> Result<Vec<Diagnostic>, CompileError> {
+    let world = CompileWorld::paged(source, options);
+    let document = world.compile::<PagedDocument>().output?;
+    Ok(missing_glyph_warnings(&world, &document)
+        .iter()
+        .map(|warning| world.diagnostic(warning))
+        .collect())
+}
+
+/// Warns about every character `document` draws as a missing glyph, once per
+/// font and character, at its first use.
+fn missing_glyph_warnings(world: &dyn World, document: &PagedDocument) -> Vec<SourceDiagnostic> {
+    fn collect(frame: &Frame, missing: &mut Vec<(String, char, (Span, u16))>) {
+        for (_, item) in frame.items() {
+            match item {
+                FrameItem::Group(group) => collect(&group.frame, missing),
+                FrameItem::Text(text) => {
+                    let family = &text.font.info().family;
+                    for glyph in text.glyphs.iter().filter(|glyph| glyph.id == 0) {
+                        for c in text.text[glyph.range()]
+                            .chars()
+                            .filter(|c| !c.is_whitespace())
+                        {
+                            if !missing.iter().any(|(f, m, _)| f == family && *m == c) {
+                                missing.push((family.clone(), c, glyph.span));
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut missing = Vec::new();
+    for page in &document.pages {
+        collect(&page.frame, &mut missing);
+    }
+    missing
+        .into_iter()
+        .map(|(family, c, (span, offset))| {
+            // The span covers the whole text run; narrow it to the character.
+            let span = match (span.id(), world.range(span)) {
+                (Some(id), Some(range)) => {
+                    let start = range.start + usize::from(offset);
+                    Span::from_range(id, start..start + c.len_utf8())
+                }
+                _ => span,
+            };
+            SourceDiagnostic::warning(
+                span,
+                eco_format!(
+                    "font '{family}' has no glyph for '{c}' (U+{:04X})",
+                    u32::from(c)
+                ),
+            )
+            .with_hint("add a font that covers it, or allow `text(fallback: true)`")
+        })
+        .collect()
+}
+
+/// A font added with [`CompileOptions::with_font`] that could not be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FontLoadError {
+    /// The font's position in [`CompileOptions::fonts`].
+    pub font: usize,
+    /// The face within a font collection, or `0` for a single font file.
+    pub face: u32,
+    /// Why the face was rejected.
+    pub message: String,
+}
+
+impl std::fmt::Display for FontLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "font {} face {}: {}", self.font, self.face, self.message)
+    }
+}
+
+/// Checks the fonts added with [`CompileOptions::with_font`] and reports each
+/// face that is skipped when fonts are loaded.
+///
+/// A font that fails to parse is left out of the font book without any
+/// diagnostic, so a document asking for it silently falls back to another
+/// font. Call this when a custom font doesn't show up to find out why.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::{font_load_errors, CompileOptions};
+///
+/// let options = CompileOptions::new().with_font(b"not a font".to_vec());
+/// for error in font_load_errors(&options) {
+///     eprintln!("{error}");
+/// }
+/// # let errors = font_load_errors(&options);
+/// # assert_eq!(errors.len(), 1);
+/// # assert_eq!(errors[0].to_string(), "font 0 face 0: unknown magic");
+/// ```
+pub fn font_load_errors(options: &CompileOptions) -> Vec<FontLoadError> {
+    let mut errors = Vec::new();
+    for (font, data) in options.fonts.iter().enumerate() {
+        // Mirrors `Font::iter`, which treats unrecognized data as a single face.
+        let faces = ttf_parser::fonts_in_collection(data).unwrap_or(1);
+        if faces == 0 {
+            errors.push(FontLoadError {
+                font,
+                face: 0,
+                message: "font collection contains no fonts".to_string(),
+            });
+        }
+        for face in 0..faces {
+            let message = match ttf_parser::Face::parse(data, face) {
+                Err(e) => e.to_string(),
+                Ok(_) if Font::new(Bytes::new(data.clone()), face).is_none() => {
+                    "font lacks the naming or shaping data Typst needs".to_string()
+                }
+                Ok(_) => continue,
+            };
+            errors.push(FontLoadError {
+                font,
+                face,
+                message,
+            });
+        }
+    }
+    errors
+}
+
+/// Compiles Typst source and returns only the HTML of a labeled region.
+///
+/// The whole document is compiled, so counters, references, and set rules
+/// behave exactly as in the full render, but only the content labeled with
+/// `<label>` is emitted. A labeled heading includes everything after it up to
+/// the next heading of the same or a higher level, so a single source can be
+/// split into many small embeds.
+///
+/// # Errors
+///
+/// Returns `CompileError::Typst` if no content carries the label.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::{compile_region, CompileOptions};
+///
+/// let source = r#"
+/// = Installation <install>
+/// Run the installer.
+/// == Linux
+/// Use the package manager.
+/// = Usage <usage>
+/// Open the app.
+/// "#;
+/// let options = CompileOptions::new();
+/// let install = compile_region(source, &options, "install").unwrap();
+/// # assert!(install.starts_with("<h2>Installation</h2>"));
+/// # assert!(install.contains("Use the package manager."));
+/// # assert!(!install.contains("Usage"));
+/// # let usage = compile_region(source, &options, "usage").unwrap();
+/// # assert_eq!(usage, "<h2>Usage</h2>\n    <p>Open the app.</p>");
+/// # assert!(compile_region(source, &options, "missing").is_err());
+/// ```
+pub fn compile_region(
+    source: &str,
+    options: &CompileOptions,
+    label: &str,
+) -> Result<String, CompileError> {
+    let world = CompileWorld::new(source, options);
+    let mut document = world.compile::<HtmlDocument>().output?;
+
+    let missing = || CompileError::Typst(format!("label <{label}> does not exist in the document"));
+    let body = document
+        .root
+        .children
+        .make_mut()
+        .iter_mut()
+        .find_map(|node| match node {
+            HtmlNode::Element(element) if element.tag.resolve().as_str() == "body" => Some(element),
+            _ => None,
+        })
+        .ok_or_else(missing)?;
+    body.children = labeled_region(&body.children, label)
+        .ok_or_else(missing)?
+        .into_iter()
+        .collect();
+
+    let html = encode_html(&document).map_err(|errors| world.error(&errors))?;
+    Ok(finish_html(body_fragment(&html).to_string(), options))
+}
+
+/// Finds the nodes produced by the content with the given label.
+///
+/// For a heading, the following siblings up to the next heading of the same or
+/// a higher level are included as well.
+fn labeled_region(nodes: &[HtmlNode], label: &str) -> Option<Vec<HtmlNode>> {
+    fn heading_level(node: &HtmlNode) -> Option<u8> {
+        let HtmlNode::Element(element) = node else {
+            return None;
+        };
+        match element.tag.resolve().as_str().as_bytes() {
+            [b'h', level @ b'1'..=b'6'] => Some(level - b'0'),
+            _ => None,
+        }
+    }
+
+    for (i, node) in nodes.iter().enumerate() {
+        match node {
+            HtmlNode::Tag(Tag::Start(elem, _))
+                if elem.label().is_some_and(|l| l.resolve().as_str() == label) =>
+            {
+                let location = elem.location();
+                let mut end = nodes[i..]
+                    .iter()
+                    .position(
+                        |n| matches!(n, HtmlNode::Tag(Tag::End(loc, ..)) if Some(*loc) == location),
+                    )
+                    .map_or(nodes.len(), |offset| i + offset + 1);
+
+                if let Some(level) = nodes[i..end].iter().find_map(heading_level) {
+                    end += nodes[end..]
+                        .iter()
+                        .position(|n| heading_level(n).is_some_and(|l| l <= level))
+                        .unwrap_or(nodes.len() - end);
+                }
+                return Some(nodes[i..end].to_vec());
+            }
+            HtmlNode::Element(element) => {
+                if let Some(region) = labeled_region(&element.children, label) {
+                    return Some(region);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Compiles Typst source and returns only the HTML produced by a range of its
+/// lines.
+///
+/// `lines` holds zero-based line indices, so `2..5` selects the third through
+/// fifth lines. Everything before the range is compiled as well, so its
+/// imports, `#let` bindings, set and show rules, and counters carry over, but
+/// its output is dropped. Lines after the range are not compiled at all.
+///
+/// The source is cut at line boundaries, without regard for its structure. A
+/// paragraph or list that starts before the range is left out entirely, even
+/// if it continues into it. The lines before the range and the lines up to its
+/// end must each compile on their own, so a range that starts or ends inside
+/// a block, such as between `#[` and its closing bracket, fails to compile.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::{compile_lines, CompileOptions};
+///
+/// let source = r#"#set heading(numbering: "1.")
+/// = Setup
+/// Install it.
+///
+/// = Usage
+/// Run it.
+///
+/// = Support
+/// "#;
+/// let usage = compile_lines(source, &CompileOptions::new(), 4..6).unwrap();
+/// # assert_eq!(usage, "<h2>2. Usage</h2>\n    <p>Run it.</p>");
+/// # assert_eq!(compile_lines(source, &CompileOptions::new(), 2..3).unwrap(), "<p>Install it.</p>");
+/// # assert_eq!(compile_lines("- a\n- b", &CompileOptions::new(), 1..2).unwrap(), "");
+/// # assert!(compile_lines("#[\nOpen\n]", &CompileOptions::new(), 1..2).is_err());
+/// ```
+pub fn compile_lines(
+    source: &str,
+    options: &CompileOptions,
+    lines: Range<usize>,
+) -> Result<String, CompileError> {
+    fn body(document: &mut HtmlDocument) -> Option<&mut HtmlElement> {
+        document
+            .root
+            .children
+            .make_mut()
+            .iter_mut()
+            .find_map(|node| match node {
+                HtmlNode::Element(element) if element.tag.resolve().as_str() == "body" => {
+                    Some(element)
+                }
+                _ => None,
+            })
+    }
+
+    let line_start =
+        |index: usize| -> usize { source.split_inclusive('\n').take(index).map(str::len).sum() };
+    let (start, end) = (line_start(lines.start), line_start(lines.end));
+
+    // The nodes the lines before the range produce on their own are the ones to
+    // drop; anything they merge with inside the range, like a paragraph that
+    // continues, is dropped along with them.
+    let mut world = CompileWorld::new(&source[..start], options);
+    let mut before = world.compile::<HtmlDocument>().output?;
+    let skip = body(&mut before).map_or(0, |body| body.children.len());
+
+    world.set_main(&source[..end.max(start)]);
+    let mut document = world.compile::<HtmlDocument>().output?;
+    if let Some(body) = body(&mut document) {
+        body.children = body.children.iter().skip(skip).cloned().collect();
+    }
+
+    let html = encode_html(&document).map_err(|errors| world.error(&errors))?;
+    Ok(finish_html(body_fragment(&html).to_string(), options))
+}
+
+/// Compiles a batch of Typst sources that share the same options.
+///
+/// Fonts, the standard library, and the provided files and packages are set up
+/// once and reused for every source, which is much cheaper than compiling each
+/// source separately. Results are returned in the same order as the sources.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::{compile_batch, CompileOptions};
+///
+/// let cards = ["*Front*", "_Back_", "#undefined"];
+/// let results = compile_batch(cards, &CompileOptions::new());
+/// # assert_eq!(results.len(), 3);
+/// # assert!(results[0].as_ref().unwrap().contains("<strong>Front</strong>"));
+/// # assert!(results[1].as_ref().unwrap().contains("<em>Back</em>"));
+/// # assert!(results[2].is_err());
+/// ```
+pub fn compile_batch<I, S>(
+    sources: I,
+    options: &CompileOptions,
+) -> Vec<Result<String, CompileError>>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut world = CompileWorld::new("", options);
+    sources
+        .into_iter()
+        .map(|source| {
+            world.set_main(source.as_ref());
+            compile_world(&world).map(|html| finish_html(html, options))
+        })
+        .collect()
+}
+
+/// Compiles many keyed Typst sources that share the same options in parallel.
+///
+/// Like [`compile_batch`], fonts, the standard library, and the provided files
+/// and packages are set up once. The sources are then spread over one worker
+/// thread per available core, each reusing that shared environment and the
+/// process-wide compilation cache. Results are keyed by the identifier given
+/// with each source, such as a page's output path in a static-site generator.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::{compile_parallel, CompileOptions};
+///
+/// let pages = [("index", "= Home"), ("about", "= About"), ("broken", "#oops")];
+/// let results = compile_parallel(pages, &CompileOptions::new());
+/// # assert_eq!(results.len(), 3);
+/// # assert_eq!(results["index"].as_ref().unwrap(), "<h2>Home</h2>");
+/// # assert_eq!(results["about"].as_ref().unwrap(), "<h2>About</h2>");
+/// # assert!(results["broken"].is_err());
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+pub fn compile_parallel<I, K, S>(
+    sources: I,
+    options: &CompileOptions,
+) -> HashMap<K, Result<String, CompileError>>
+where
+    I: IntoIterator<Item = (K, S)>,
+    K: Eq + Hash + Send,
+    S: AsRef<str> + Send,
+{
+    let sources: Vec<_> = sources.into_iter().collect();
+    let workers = std::thread::available_parallelism()
+        .map_or(1, usize::from)
+        .min(sources.len());
+    let queue = std::sync::Mutex::new(sources.into_iter());
+    let world = CompileWorld::new("", options);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                let (queue, mut world) = (&queue, world.clone());
+                scope.spawn(move || {
+                    let mut results = Vec::new();
+                    // Take the lock only to pull the next source, not while compiling.
+                    while let Some((key, source)) = queue
+                        .lock()
+                        .unwrap_or_else(std::sync::PoisonError::into_inner)
+                        .next()
+                    {
+                        world.set_main(source.as_ref());
+                        // A panic fails only this source, keeping the worker's
+                        // other results.
+                        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                            compile_world(&world).map(|html| finish_html(html, options))
+                        }))
+                        .unwrap_or_else(|_| {
+                            Err(CompileError::Typst("compilation panicked".to_string()))
+                        });
+                        results.push((key, result));
+                    }
+                    results
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap_or_default())
+            .collect()
+    })
+}
+
+/// Compiles Typst source to an HTML fragment on an executor of your choice.
+///
+/// `spawn` receives the compilation as a job and should run it elsewhere, for
+/// example with `|job| pool.spawn(job)` on a `rayon::ThreadPool`, or by
+/// sending it to a fixed set of worker threads. The returned handle resolves
+/// once the job has run, so a server can bound how many documents compile at
+/// once by sizing its pool instead of compiling on every request thread.
+/// Combine this with [`CompileOptions::with_cancellation`] to abandon jobs
+/// that are no longer wanted.
+///
+/// Not available on WebAssembly.
+///
+/// # Errors
+///
+/// The handle resolves to the compilation's error, or to
+/// `CompileError::Typst` if the executor dropped the job without running it.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::{compile_html_on, CompileOptions};
+///
+/// # fn block_on<F: std::future::Future>(future: F) -> F::Output {
+/// #     struct Unpark(std::thread::Thread);
+/// #     impl std::task::Wake for Unpark {
+/// #         fn wake(self: std::sync::Arc<Self>) { self.0.unpark() }
+/// #     }
+/// #     let waker = std::sync::Arc::new(Unpark(std::thread::current())).into();
+/// #     let mut context = std::task::Context::from_waker(&waker);
+/// #     let mut future = std::pin::pin!(future);
+/// #     loop {
+/// #         match future.as_mut().poll(&mut context) {
+/// #             std::task::Poll::Ready(output) => return output,
+/// #             std::task::Poll::Pending => std::thread::park(),
+/// #         }
+/// #     }
+/// # }
+/// let handle = compile_html_on("= Report", &CompileOptions::new(), |job| {
+///     std::thread::spawn(job);
+/// });
+/// let html = block_on(handle).unwrap();
+/// # assert_eq!(html, "<h2>Report</h2>");
+/// # let dropped = compile_html_on("= Report", &CompileOptions::new(), drop);
+/// # assert!(block_on(dropped).is_err());
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+pub fn compile_html_on(
+    source: &str,
+    options: &CompileOptions,
+    spawn: impl FnOnce(Box<dyn FnOnce() + Send>),
+) -> CompileHandle {
+    let (sender, receiver) = futures_channel::oneshot::channel();
+    let (source, options) = (source.to_string(), options.clone());
+    spawn(Box::new(move || {
+        let _ = sender.send(compile_html(&source, &options));
+    }));
+    CompileHandle(receiver)
+}
+
+/// The eventual result of [`compile_html_on`].
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+pub struct CompileHandle(futures_channel::oneshot::Receiver<Result<String, CompileError>>);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl std::future::Future for CompileHandle {
+    type Output = Result<String, CompileError>;
+
+    fn poll(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        std::pin::Pin::new(&mut self.0).poll(cx).map(|result| {
+            result.unwrap_or_else(|_| {
+                Err(CompileError::Typst(
+                    "compilation was dropped before it ran".to_string(),
+                ))
+            })
+        })
+    }
+}
+
+/// One line of a diff between two compiled documents, see [`compile_diff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HtmlChange {
+    /// A line present in both versions.
+    Unchanged(String),
+    /// A line only in the new version.
+    Added(String),
+    /// A line only in the old version.
+    Removed(String),
+}
+
+/// Compiles two versions of a document with the same options and diffs their
+/// HTML line by line.
+///
+/// Typst writes each block element on its own line, so lines correspond to
+/// headings, paragraphs, list items, table cells, and so on. Indentation is
+/// dropped before comparing, so moving content in or out of a container only
+/// shows the container's lines as changed. An element that was edited shows
+/// up as its old line [`Removed`](HtmlChange::Removed) followed by its new line
+/// [`Added`](HtmlChange::Added).
+///
+/// # Errors
+///
+/// Returns the error of the first version that fails to compile.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::{compile_diff, CompileOptions, HtmlChange};
+///
+/// let old = "= Intro\n\nFirst draft.";
+/// let new = "= Intro\n\nSecond draft.\n\nA new paragraph.";
+/// let changes = compile_diff(old, new, &CompileOptions::new()).unwrap();
+/// for change in &changes {
+///     match change {
+///         HtmlChange::Added(line) => println!("+ {line}"),
+///         HtmlChange::Removed(line) => println!("- {line}"),
+///         HtmlChange::Unchanged(_) => {}
+///     }
+/// }
+/// # assert_eq!(
+/// #     changes,
+/// #     [
+/// #         HtmlChange::Unchanged("<h2>Intro</h2>".into()),
+/// #         HtmlChange::Removed("<p>First draft.</p>".into()),
+/// #         HtmlChange::Added("<p>Second draft.</p>".into()),
+/// #         HtmlChange::Added("<p>A new paragraph.</p>".into()),
+/// #     ],
+/// # );
+/// ```
+pub fn compile_diff(
+    old: &str,
+    new: &str,
+    options: &CompileOptions,
+) -> Result<Vec<HtmlChange>, CompileError> {
+    let options = CompileOptions {
+        format: None,
+        ..options.clone()
+    };
+    let mut results = compile_batch([old, new], &options).into_iter();
+    let (Some(old), Some(new)) = (results.next(), results.next()) else {
+        unreachable!("two sources were compiled");
+    };
+    let (old, new) = (old?, new?);
+    let lines = |html: &str| -> Vec<String> {
+        html.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect()
+    };
+    Ok(diff_lines(&lines(&old), &lines(&new)))
+}
+
+/// Diffs two sequences of lines using their longest common subsequence.
+fn diff_lines(old: &[String], new: &[String]) -> Vec<HtmlChange> {
+    // Shared leading and trailing lines are common for small edits and keep
+    // the quadratic table small.
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (a, b) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    // lcs[i][j] is the length of the longest common subsequence of a[i..] and b[j..].
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes: Vec<HtmlChange> = old[..prefix]
+        .iter()
+        .cloned()
+        .map(HtmlChange::Unchanged)
+        .collect();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            changes.push(HtmlChange::Unchanged(a[i].clone()));
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            changes.push(HtmlChange::Removed(a[i].clone()));
+            i += 1;
+        } else {
+            changes.push(HtmlChange::Added(b[j].clone()));
+            j += 1;
+        }
+    }
+    changes.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .cloned()
+            .map(HtmlChange::Unchanged),
+    );
+    changes
+}
+
+/// A Dioxus component that renders Typst markup as HTML.
+///
+/// This component compiles the provided Typst source at runtime and renders the
+/// resulting HTML. Compilation errors are displayed inline.
+///
+/// # Props
+///
+/// - `source`: The Typst source code to compile.
+/// - `options`: Optional [`CompileOptions`] providing additional files and
+///   packages, as a value or a signal. Merged over options provided as
+///   context, see [app-wide options](#app-wide-options).
+/// - `class`: CSS class for the wrapper div (defaults to `"typst-content"`).
+/// - `css`: Optional stylesheet shipped with the document, scoped to the
+///   wrapper class.
+/// - `error_class`: CSS class for the error div (defaults to `"typst-error"`).
+/// - `hide_errors`: Render nothing instead of the error div.
+/// - `on_error`: Called with each new compilation error.
+/// - `max_html_len`: Size in bytes above which the HTML is not inlined, see
+///   [large documents](#large-documents).
+/// - `on_oversized`: Receives HTML over `max_html_len` instead of the wrapper.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus::prelude::*;
+/// use dioxus_typst::Typst;
+///
+/// #[component]
+/// fn App() -> Element {
+///     let content = r#"
+/// = Hello, Typst!
+///
+/// Some *formatted* text with math: $E = m c^2$
+/// "#;
+///
+///     rsx! {
+///         Typst { source: content.to_string() }
+///     }
+/// }
+/// ```
+///
+/// # Styling
+///
+/// The component outputs semantic HTML without styling. Apply CSS to the wrapper
+/// class to style headings, paragraphs, code blocks, and other elements. The
+/// one exception is a document's own colors: a solid `#set page(fill: ..)` and
+/// `#set text(fill: ..)` become the wrapper's background and text color, see
+/// [`PageStyle`].
+///
+/// Alternatively, pass a stylesheet through the `css` prop. Its rules are nested
+/// under the wrapper class, so `h1 { color: red; }` only affects headings inside
+/// this component:
+///
+/// ```rust
+/// use dioxus::prelude::*;
+/// use dioxus_typst::Typst;
+///
+/// #[component]
+/// fn App() -> Element {
+///     rsx! {
+///         Typst {
+///             source: "= Styled".to_string(),
+///             css: "h2 { font-variant: small-caps; }".to_string(),
+///         }
+///     }
+/// }
+/// ```
+///
+/// This relies on CSS nesting, supported since Chrome 120, Firefox 117, and
+/// Safari 17.2. Older browsers drop the nested rules; for those, style the
+/// wrapper class directly or use `shadow`, which scopes the stylesheet without
+/// nesting.
+///
+/// # Errors
+///
+/// Compilation errors are rendered as a `<div class="typst-error">` containing
+/// the error message, or a `<ul>` of messages when there are several. Style this
+/// class to make errors visible during development, or pick another one with
+/// `error_class`. Include the wrapper class in it to style both states alike:
+///
+/// ```rust
+/// use dioxus::prelude::*;
+/// use dioxus_typst::Typst;
+///
+/// #[component]
+/// fn Themed(source: String) -> Element {
+///     rsx! {
+///         Typst { source, class: "doc", error_class: "doc doc-error" }
+///     }
+/// }
+/// ```
+///
+/// To present errors yourself, set `on_error` to receive each new error as it
+/// occurs, and `hide_errors` to render nothing in place of the error `<div>`:
+///
+/// ```rust
+/// use dioxus::prelude::*;
+/// use dioxus_typst::Typst;
+///
+/// #[component]
+/// fn Editor(draft: String) -> Element {
+///     let mut status = use_signal(String::new);
+///     rsx! {
+///         p { class: "status", "{status}" }
+///         Typst {
+///             source: draft,
+///             hide_errors: true,
+///             on_error: move |error| status.set(format!("Cannot render: {error}")),
+///         }
+///     }
+/// }
+/// ```
+///
+/// # Style isolation
+///
+/// Set `shadow` to render the document into a shadow root attached to the
+/// wrapper `<div>`, so the app's stylesheets don't reach the document and the
+/// `css` prop doesn't leak out. The shadow root is filled in by a script once
+/// the component is mounted, so this needs a renderer that runs JavaScript,
+/// such as web, desktop, or mobile; server-rendered HTML contains only the
+/// empty wrapper.
+///
+/// ```rust
+/// use dioxus::prelude::*;
+/// use dioxus_typst::Typst;
+///
+/// #[component]
+/// fn Embed(source: String) -> Element {
+///     rsx! {
+///         Typst { source, shadow: true, css: "p { margin: 0; }".to_string() }
+///     }
+/// }
+/// ```
+///
+/// Set `keep_previous` to keep showing the last successful render below the
+/// error while the source is invalid, instead of replacing it. In a live editor
+/// this keeps the preview stable while the user is mid-edit:
+///
+/// ```rust
+/// use dioxus::prelude::*;
+/// use dioxus_typst::Typst;
+///
+/// #[component]
+/// fn Preview(draft: String) -> Element {
+///     rsx! {
+///         Typst { source: draft, keep_previous: true }
+///     }
+/// }
+/// ```
+///
+/// # Large documents
+///
+/// Inlining megabytes of HTML at once can stall the browser. Set
+/// `max_html_len` to a size in bytes, and `on_oversized` to receive any output
+/// larger than that instead: the wrapper `<div>` is then rendered empty, and
+/// the callback can paginate the HTML or mount it lazily. Without
+/// `on_oversized`, such output is still inlined, but a warning is logged.
+///
+/// ```rust
+/// use dioxus::prelude::*;
+/// use dioxus_typst::Typst;
+///
+/// #[component]
+/// fn Book(source: String) -> Element {
+///     let mut deferred = use_signal(|| None::<String>);
+///     let mut shown = use_signal(|| false);
+///     rsx! {
+///         Typst {
+///             source,
+///             max_html_len: 1 << 20,
+///             on_oversized: move |html| deferred.set(Some(html)),
+///         }
+///         if let Some(html) = deferred() {
+///             if shown() {
+///                 div { dangerous_inner_html: "{html}" }
+///             } else {
+///                 button { onclick: move |_| shown.set(true), "Show the full document" }
+///             }
+///         }
+///     }
+/// }
+/// ```
+///
+/// # Sharing options
+///
+/// Options holding fonts, images, or packages can be large. Rather than
+/// rebuilding or cloning them on every render, keep them in a signal or memo
+/// and pass that: only the signal handle is copied, and the document is
+/// recompiled whenever the signal changes.
+///
+/// Only `source` and `options` cause a recompile. Changing a presentational
+/// prop such as `class`, `css`, or `error_class` re-renders the wrapper around
+/// the previous result, so switching themes never compiles the document again.
+///
+/// ```rust
+/// use dioxus::prelude::*;
+/// use dioxus_typst::{CompileOptions, Typst};
+///
+/// #[component]
+/// fn Report(source: String, logo: Vec<u8>) -> Element {
+///     let options = use_memo(use_reactive!(|logo| {
+///         CompileOptions::new().with_file("logo.png", logo)
+///     }));
+///     rsx! {
+///         Typst { source, options }
+///     }
+/// }
+/// ```
+///
+/// # App-wide options
+///
+/// Fonts and packages are usually the same for every document in an app.
+/// Provide them once near the root as a `Signal<CompileOptions>` context, and
+/// every `Typst` below inherits them. An `options` prop is
+/// [merged](CompileOptions::merge) over the inherited options, so an instance
+/// can add a file or change a setting while keeping the shared fonts.
+///
+/// ```rust
+/// use dioxus::prelude::*;
+/// use dioxus_typst::{CompileOptions, Typst};
+///
+/// #[component]
+/// fn App(font: Vec<u8>) -> Element {
+///     use_context_provider(|| Signal::new(CompileOptions::new().with_font(font)));
+///     rsx! {
+///         Typst { source: "= Shared fonts" }
+///         Typst {
+///             source: "#image(\"chart.svg\")",
+///             options: CompileOptions::new().with_file("chart.svg", Vec::new()),
+///         }
+///     }
+/// }
+/// ```
+///
+/// # Debouncing
+///
+/// Set `debounce` to wait until `source` has stopped changing for that long
+/// before recompiling, so typing into a bound text area compiles once per pause
+/// rather than on every keystroke. The first render compiles immediately.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use dioxus::prelude::*;
+/// use dioxus_typst::Typst;
+///
+/// #[component]
+/// fn Editor() -> Element {
+///     let mut draft = use_signal(|| "= Draft".to_string());
+///     rsx! {
+///         textarea { value: draft, oninput: move |e| draft.set(e.value()) }
+///         Typst { source: draft(), debounce: Duration::from_millis(300) }
+///     }
+/// }
+/// ```
+#[component]
+pub fn Typst(
+    source: String,
+    #[props(default)] options: ReadSignal<CompileOptions>,
+    #[props(default = "typst-content".to_string())] class: String,
+    css: Option<String>,
+    #[props(default = "typst-error".to_string())] error_class: String,
+    #[props(default)] hide_errors: bool,
+    on_error: Option<EventHandler<CompileError>>,
+    #[props(default)] keep_previous: bool,
+    debounce: Option<Duration>,
+    #[props(default)] shadow: bool,
+    max_html_len: Option<usize>,
+    on_oversized: Option<EventHandler<String>>,
+) -> Element {
+    let previous = use_hook(|| Rc::new(RefCell::new(None::<Rc<(String, String)>>)));
+    let host_id = use_hook(|| {
+        static NEXT_HOST: AtomicUsize = AtomicUsize::new(0);
+        format!("typst-shadow-{}", NEXT_HOST.fetch_add(1, Ordering::Relaxed))
+    });
+    let mut settled = use_signal(|| source.clone());
+    let mut timer = use_signal(|| None::<dioxus::core::Task>);
+
+    use_effect(use_reactive!(|source, debounce| {
+        let Some(delay) = debounce else {
+            return;
+        };
+        if let Some(task) = timer.take() {
+            task.cancel();
+        }
+        timer.set(Some(spawn(async move {
+            sleep(delay).await;
+            if *settled.peek() != source {
+                settled.set(source);
+            }
+        })));
+    }));
+
+    let source = match debounce {
+        Some(_) => settled.read().clone(),
+        None => source,
+    };
+    let inherited = try_use_context::<Signal<CompileOptions>>();
+    // Merging with default options changes nothing, so the inherited ones are
+    // only copied when the prop adds to them, and again only when either changes.
+    let merged = use_memo(move || {
+        let options = options.read();
+        let inherited = inherited?;
+        (*options != CompileOptions::default())
+            .then(|| inherited.read().clone().merge(options.clone()))
+    });
+    // Only the source and options affect the document, so a render caused by
+    // any other prop reuses the previous result.
+    let compiled = use_memo(use_reactive!(|source| {
+        match (&*merged.read(), inherited) {
+            (Some(merged), _) => compile_html_styled(&source, merged),
+            (None, Some(inherited)) => compile_html_styled(&source, &inherited.read()),
+            (None, None) => compile_html_styled(&source, &options.read()),
+        }
+    }));
+    // The HTML with its stylesheet, and the page CSS, rebuilt only when the
+    // document or a prop they depend on changes.
+    let rendered = use_memo(use_reactive!(|class, css, shadow, keep_previous| {
+        match &*compiled.read() {
+            Ok((html, page_style)) => {
+                // A shadow root already isolates its contents, so the stylesheet is
+                // used as is rather than scoped to the wrapper class.
+                let style = if shadow {
+                    css.as_deref().map(style_element)
+                } else {
+                    css.as_deref().and_then(|css| scoped_style(&class, css))
+                };
+                let html = match style {
+                    Some(style) => format!("{style}{html}"),
+                    None => html.clone(),
+                };
+                let rendered = Rc::new((html, page_style.css()));
+                if keep_previous {
+                    *previous.borrow_mut() = Some(rendered.clone());
+                }
+                Some(rendered)
+            }
+            Err(_) => previous.borrow().clone().filter(|_| keep_previous),
+        }
+    }));
+
+    let oversized = use_memo(use_reactive!(|max_html_len| {
+        rendered
+            .read()
+            .clone()
+            .filter(|rendered| max_html_len.is_some_and(|max| rendered.0.len() > max))
+    }));
+    let handed_off = oversized.read().is_some() && on_oversized.is_some();
+    use_effect(move || {
+        let Some(rendered) = oversized() else {
+            return;
+        };
+        match on_oversized {
+            Some(on_oversized) => on_oversized.call(rendered.0.clone()),
+            None => dioxus::logger::tracing::warn!(
+                "Typst output is {} bytes, over the {} byte limit; set `on_oversized` to mount it yourself",
+                rendered.0.len(),
+                max_html_len.unwrap_or_default(),
+            ),
+        }
+    });
+
+    let shadow_host = (shadow && !handed_off).then(|| host_id.clone());
+    use_effect(use_reactive!(|shadow_host| {
+        if let (Some(host_id), Some(rendered)) = (shadow_host, &*rendered.read()) {
+            document::eval(&format!(
+                "const host = document.getElementById({}); \
+                 if (host) {{ (host.shadowRoot ?? host.attachShadow({{ mode: 'open' }})).innerHTML = {}; }}",
+                js_string(&host_id),
+                js_string(&rendered.0),
+            ));
+        }
+    }));
+
+    // The memo only changes when a compilation gives a different result, so
+    // each error is reported once rather than on every render that still has it.
+    use_effect(move || {
+        if let (Err(error), Some(on_error)) = (&*compiled.read(), on_error) {
+            on_error.call(error.clone());
+        }
+    });
+
+    rsx! {
+        if let Err(e) = &*compiled.read() {
+            if !hide_errors {
+                {error_view(e, &error_class)}
+            }
+        }
+        TypstOutput {
+            rendered,
+            class,
+            host_id: shadow.then_some(host_id),
+            handed_off,
+        }
+    }
+}
+
+/// The wrapper `<div>` of a [`Typst`] component.
+///
+/// Kept apart so that renders of the parent caused by other props leave the
+/// HTML where it is instead of copying it into a new node.
+#[component]
+fn TypstOutput(
+    rendered: Memo<Option<Rc<(String, String)>>>,
+    class: String,
+    host_id: Option<String>,
+    handed_off: bool,
+) -> Element {
+    let rendered = rendered.read();
+    let Some(rendered) = rendered.as_ref() else {
+        return rsx! {};
+    };
+    let (html, page_css) = &**rendered;
+    rsx! {
+        if handed_off {
+            div { class, style: "{page_css}" }
+        } else if let Some(id) = host_id {
+            div { id, class, style: "{page_css}" }
+        } else {
+            div { class, style: "{page_css}", dangerous_inner_html: "{html}" }
+        }
+    }
+}
+
+/// Configures a [`Typst`] component fluently and renders it with one call.
+///
+/// Each `with_*` method sets the prop of the same name, and props left unset
+/// keep the component's defaults. This reads better than a long prop list
+/// when a component uses many of them. Callbacks are attached to the
+/// component that calls them, so build the component inside a render
+/// function.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use dioxus::prelude::*;
+/// use dioxus_typst::{CompileOptions, TypstBuilder};
+///
+/// #[component]
+/// fn Preview(draft: String) -> Element {
+///     let mut status = use_signal(String::new);
+///     rsx! {
+///         p { "{status}" }
+///         {TypstBuilder::new(draft)
+///             .with_options(CompileOptions::new().with_lenient_references(true))
+///             .with_class("preview")
+///             .with_css("h2 { color: teal; }")
+///             .with_keep_previous(true)
+///             .with_debounce(Duration::from_millis(300))
+///             .with_on_error(move |error| status.set(error.to_string()))
+///             .render()}
+///     }
+/// }
+/// ```
+#[derive(Clone)]
+pub struct TypstBuilder {
+    source: String,
+    options: CompileOptions,
+    class: String,
+    css: Option<String>,
+    error_class: String,
+    hide_errors: bool,
+    on_error: Option<EventHandler<CompileError>>,
+    keep_previous: bool,
+    debounce: Option<Duration>,
+    shadow: bool,
+    max_html_len: Option<usize>,
+    on_oversized: Option<EventHandler<String>>,
+}
+
+impl TypstBuilder {
+    /// Starts configuring a component that renders `source`.
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            options: CompileOptions::default(),
+            class: "typst-content".to_string(),
+            css: None,
+            error_class: "typst-error".to_string(),
+            hide_errors: false,
+            on_error: None,
+            keep_previous: false,
+            debounce: None,
+            shadow: false,
+            max_html_len: None,
+            on_oversized: None,
+        }
+    }
+
+    /// Sets the compilation options.
+    #[must_use]
+    pub fn with_options(mut self, options: CompileOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sets the CSS class of the wrapper `<div>`.
+    #[must_use]
+    pub fn with_class(mut self, class: impl Into<String>) -> Self {
+        self.class = class.into();
+        self
+    }
+
+    /// Sets a stylesheet shipped with the document.
+    #[must_use]
+    pub fn with_css(mut self, css: impl Into<String>) -> Self {
+        self.css = Some(css.into());
+        self
+    }
+
+    /// Sets the CSS class of the error `<div>`.
+    #[must_use]
+    pub fn with_error_class(mut self, class: impl Into<String>) -> Self {
+        self.error_class = class.into();
+        self
+    }
+
+    /// Sets whether errors render nothing instead of the error `<div>`.
+    #[must_use]
+    pub fn with_hide_errors(mut self, hide: bool) -> Self {
+        self.hide_errors = hide;
+        self
+    }
+
+    /// Sets a callback receiving each new compilation error.
+    #[must_use]
+    pub fn with_on_error(mut self, on_error: impl FnMut(CompileError) + 'static) -> Self {
+        self.on_error = Some(EventHandler::new(on_error));
+        self
+    }
+
+    /// Sets whether the last successful render stays visible on errors.
+    #[must_use]
+    pub fn with_keep_previous(mut self, keep: bool) -> Self {
+        self.keep_previous = keep;
+        self
+    }
+
+    /// Waits for the source to stop changing for `delay` before recompiling.
+    #[must_use]
+    pub fn with_debounce(mut self, delay: Duration) -> Self {
+        self.debounce = Some(delay);
+        self
+    }
+
+    /// Sets whether the document renders into a shadow root.
+    #[must_use]
+    pub fn with_shadow(mut self, shadow: bool) -> Self {
+        self.shadow = shadow;
+        self
+    }
+
+    /// Sets the size in bytes above which the HTML is not inlined.
+    #[must_use]
+    pub fn with_max_html_len(mut self, max: usize) -> Self {
+        self.max_html_len = Some(max);
+        self
+    }
+
+    /// Sets a callback receiving HTML over the size limit instead of the wrapper.
+    #[must_use]
+    pub fn with_on_oversized(mut self, on_oversized: impl FnMut(String) + 'static) -> Self {
+        self.on_oversized = Some(EventHandler::new(on_oversized));
+        self
+    }
+
+    /// Renders the configured component.
+    pub fn render(self) -> Element {
+        rsx! {
+            Typst {
+                source: self.source,
+                options: self.options,
+                class: self.class,
+                css: self.css,
+                error_class: self.error_class,
+                hide_errors: self.hide_errors,
+                on_error: self.on_error,
+                keep_previous: self.keep_previous,
+                debounce: self.debounce,
+                shadow: self.shadow,
+                max_html_len: self.max_html_len,
+                on_oversized: self.on_oversized,
+            }
+        }
+    }
+}
+
+/// Quotes text as a JavaScript string literal that is also safe inside HTML.
+fn js_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '<' => out.push_str("\\u003c"),
+            '\u{2028}' => out.push_str("\\u2028"),
+            '\u{2029}' => out.push_str("\\u2029"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Waits for `duration` without blocking the UI thread.
+async fn sleep(duration: Duration) {
+    if duration.is_zero() {
+        return;
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    gloo_timers::future::sleep(duration).await;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let (sender, receiver) = futures_channel::oneshot::channel();
+        if TIMER.send((Instant::now() + duration, sender)).is_ok() {
+            let _ = receiver.await;
+        }
+    }
+}
+
+/// A sleep waiting on [`TIMER`]: its deadline, and the channel to wake it through.
+#[cfg(not(target_arch = "wasm32"))]
+type Wakeup = (Instant, futures_channel::oneshot::Sender<()>);
+
+/// Wakes pending sleeps from one shared thread.
+///
+/// Dioxus doesn't expose a timer, and renderers run different executors, so
+/// tasks are woken from a thread instead of relying on one. Sleeps whose task
+/// was cancelled are dropped the next time the thread wakes up.
+#[cfg(not(target_arch = "wasm32"))]
+static TIMER: LazyLock<std::sync::mpsc::Sender<Wakeup>> = LazyLock::new(|| {
+    let (requests, incoming) = std::sync::mpsc::channel::<Wakeup>();
+    // If the thread can't be spawned, `incoming` is dropped with it and every
+    // sleep returns right away.
+    let _ = std::thread::Builder::new()
+        .name("dioxus-typst-timer".into())
+        .spawn(move || {
+            let mut pending = Vec::<Wakeup>::new();
+            loop {
+                let now = Instant::now();
+                let (due, waiting): (Vec<_>, Vec<_>) = pending
+                    .into_iter()
+                    .partition(|(deadline, _)| *deadline <= now);
+                for (_, sender) in due {
+                    let _ = sender.send(());
+                }
+                pending = waiting
+                    .into_iter()
+                    .filter(|(_, sender)| !sender.is_canceled())
+                    .collect();
+                let request = match pending.iter().map(|(deadline, _)| *deadline).min() {
+                    Some(next) => match incoming.recv_timeout(next - now) {
+                        Ok(request) => request,
+                        Err(std::sync::mpsc::RecvTimeoutError::Timeout) => continue,
+                        Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => return,
+                    },
+                    None => match incoming.recv() {
+                        Ok(request) => request,
+                        Err(_) => return,
+                    },
+                };
+                pending.push(request);
+            }
+        });
+    requests
+});
+
+/// Renders a compilation error for the [`Typst`] component.
+fn error_view(e: &CompileError, class: &str) -> Element {
+    match e.messages().as_slice() {
+        [_] => rsx! {
+            div { class, "Error compiling Typst: {e}" }
+        },
+        messages => rsx! {
+            div { class,
+                "Error compiling Typst:"
+                ul {
+                    for message in messages {
+                        li { "{message}" }
+                    }
+                }
+            }
+        },
+    }
+}
+
+/// A URL referenced from an element in compiled HTML.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceRef {
+    /// The element's tag name, such as `img` or `a`.
+    pub tag: String,
+    /// The attribute holding the URL, such as `src` or `href`.
+    pub attribute: String,
+    /// The URL, with HTML character references decoded.
+    pub url: String,
+}
+
+impl ResourceRef {
+    /// Whether the resource is embedded as a `data:` URI and needs no serving.
+    pub fn is_inline(&self) -> bool {
+        self.url.starts_with("data:")
+    }
+
+    /// Whether the URL points to an element of the same document, like `#intro`.
+    pub fn is_fragment(&self) -> bool {
+        self.url.starts_with('#')
+    }
+}
+
+/// Lists every URL referenced by `src` and `href` attributes in compiled HTML.
+///
+/// Pass the output of [`compile_html`] or [`compile_full_html`]. References are
+/// returned in document order, including those inside SVG frames. Filtering
+/// out [`is_inline`](ResourceRef::is_inline) and
+/// [`is_fragment`](ResourceRef::is_fragment) references leaves the ones that
+/// must be served alongside the HTML, which makes broken links easy to catch
+/// before shipping.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::{compile_html, resource_refs, CompileOptions};
+///
+/// let svg = br#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10"/>"#;
+/// let options = CompileOptions::new().with_file("logo.svg", svg.to_vec());
+/// let source = r#"
+/// #image("/logo.svg")
+/// #link("https://typst.app/?a=1&b=2")[Typst]
+/// "#;
+/// let html = compile_html(source, &options).unwrap();
+/// let external: Vec<String> = resource_refs(&html)
+///     .into_iter()
+///     .filter(|r| !r.is_inline() && !r.is_fragment())
+///     .map(|r| r.url)
+///     .collect();
+/// # assert_eq!(external, ["https://typst.app/?a=1&b=2"]);
+/// # let refs = resource_refs(&html);
+/// # assert_eq!((refs[0].tag.as_str(), refs[0].attribute.as_str()), ("img", "src"));
+/// # assert!(refs[0].is_inline());
+/// ```
+pub fn resource_refs(html: &str) -> Vec<ResourceRef> {
+    let mut refs = Vec::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let end = tag_end(rest);
+        let tag = &rest[1..end];
+        rest = &rest[end..];
+
+        let tag = tag.trim_end_matches('>').trim_end_matches('/');
+        let name_end = tag
+            .find(|c: char| c.is_ascii_whitespace())
+            .unwrap_or(tag.len());
+        let (name, mut attributes) = tag.split_at(name_end);
+        if name.is_empty() || name.starts_with(['/', '!']) {
+            continue;
+        }
+        while let Some((attribute, value, remaining)) = next_attribute(attributes) {
+            attributes = remaining;
+            if attribute.eq_ignore_ascii_case("src")
+                || attribute.eq_ignore_ascii_case("href")
+                || attribute.ends_with(":href")
+            {
+                refs.push(ResourceRef {
+                    tag: name.to_string(),
+                    attribute: attribute.to_string(),
+                    url: decode_entities(value),
+                });
+            }
+        }
+    }
+    refs
+}
+
+/// Splits the first `name="value"` attribute off the inside of a start tag,
+/// returning the name, the raw value, and the remaining attributes.
+fn next_attribute(attributes: &str) -> Option<(&str, &str, &str)> {
+    let attributes = attributes.trim_start();
+    let name_end = attributes
+        .find(|c: char| c.is_ascii_whitespace() || c == '=')
+        .unwrap_or(attributes.len());
+    if name_end == 0 {
+        return None;
+    }
+    let (name, rest) = attributes.split_at(name_end);
+    let Some(rest) = rest.trim_start().strip_prefix('=') else {
+        return Some((name, "", rest));
+    };
+    let rest = rest.trim_start();
+    let (value, rest) = match rest.chars().next() {
+        Some(quote @ ('"' | '\'')) => {
+            let rest = &rest[1..];
+            let end = rest.find(quote).unwrap_or(rest.len());
+            (&rest[..end], rest.get(end + 1..).unwrap_or_default())
+        }
+        _ => {
+            let end = rest
+                .find(|c: char| c.is_ascii_whitespace())
+                .unwrap_or(rest.len());
+            rest.split_at(end)
+        }
+    };
+    Some((name, value, rest))
+}
+
+/// Decodes the character references Typst's HTML exporter writes.
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').and_then(|end| {
+            let c = match &rest[1..end] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                hex => char::from_u32(u32::from_str_radix(hex.strip_prefix("#x")?, 16).ok()?)?,
+            };
+            Some((c, end + 1))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Parses the `typst.toml` manifest from a package's files.
+///
+/// Accepts the same file map passed to [`CompileOptions::with_package`], with
+/// or without leading slashes. The manifest exposes the package's declared
+/// name, version, entrypoint, description, and other fields, which is handy for
+/// listing loaded packages. Call [`PackageManifest::validate`] to check that it
+/// matches the spec the package was loaded under.
+///
+/// # Errors
+///
+/// Returns `CompileError::Typst` if there is no `typst.toml` or it is not a
+/// valid package manifest.
+///
+/// # Example
+///
+/// ```rust
+/// use std::{collections::HashMap, str::FromStr};
+/// use dioxus_typst::{package_manifest, PackageSpec};
+///
+/// let manifest = br#"
+/// [package]
+/// name = "example"
+/// version = "0.1.0"
+/// entrypoint = "lib.typ"
+/// description = "An example package"
+/// "#;
+/// let files = HashMap::from([("typst.toml".to_string(), manifest.to_vec())]);
+///
+/// let manifest = package_manifest(&files).unwrap();
+/// # assert_eq!(manifest.package.name, "example");
+/// # assert_eq!(manifest.package.entrypoint, "lib.typ");
+/// # assert_eq!(manifest.package.description.as_deref(), Some("An example package"));
+/// let spec = PackageSpec::from_str("@preview/example:0.1.0").unwrap();
+/// assert!(manifest.validate(&spec).is_ok());
+/// # let other = PackageSpec::from_str("@preview/example:0.2.0").unwrap();
+/// # assert!(manifest.validate(&other).is_err());
+/// # assert!(package_manifest(&HashMap::new()).is_err());
+/// ```
+pub fn package_manifest(files: &HashMap<String, Vec<u8>>) -> Result<PackageManifest, CompileError> {
+    let data = files
+        .get("/typst.toml")
+        .or_else(|| files.get("typst.toml"))
+        .ok_or_else(|| CompileError::Typst("package has no typst.toml".to_string()))?;
+    let text = std::str::from_utf8(data)
+        .map_err(|_| CompileError::Typst("typst.toml is not valid UTF-8".to_string()))?;
+    toml::from_str(text)
+        .map_err(|e| CompileError::Typst(format!("invalid typst.toml: {}", e.message())))
+}
+
+/// Names a package from its namespace, name, and version, for
+/// [`CompileOptions::with_package`].
+///
+/// This is the same as parsing `@{namespace}/{name}:{version}` into a
+/// [`PackageSpec`], without having to build the string or import `FromStr`.
+///
+/// # Errors
+///
+/// Returns `CompileError::Typst` if a part is not valid, such as a name that
+/// isn't an identifier or a version that isn't `major.minor.patch`.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::package_spec;
+///
+/// let spec = package_spec("preview", "cetz", "0.4.2").unwrap();
+/// # assert_eq!(spec.to_string(), "@preview/cetz:0.4.2");
+/// # assert_eq!(spec.version.minor, 4);
+/// # assert!(package_spec("preview", "cetz", "latest").is_err());
+/// # assert!(package_spec("preview", "not a name", "0.1.0").is_err());
+/// ```
+pub fn package_spec(
+    namespace: &str,
+    name: &str,
+    version: &str,
+) -> Result<PackageSpec, CompileError> {
+    format!("@{namespace}/{name}:{version}")
+        .parse()
+        .map_err(|e: EcoString| CompileError::Typst(e.to_string()))
+}
+
+/// The packages a document imports, split by whether they could be found.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageReport {
+    /// Packages found in one of the package sources, in order of first import.
+    pub resolved: Vec<PackageSpec>,
+    /// Packages that could not be loaded, each with the reason.
+    pub unresolved: Vec<(PackageSpec, String)>,
+}
+
+impl PackageReport {
+    /// Returns whether every imported package was found.
+    pub fn is_complete(&self) -> bool {
+        self.unresolved.is_empty()
+    }
+}
+
+/// Checks that every package a document imports can be loaded, without
+/// compiling it.
+///
+/// The document is only parsed. Every `#import` and `#include` of a string is
+/// followed: local files through the provided files, and packages through the
+/// package sources in the options, including the packages those packages
+/// import in turn. A package resolves when its `typst.toml` can be read and
+/// parsed and its entrypoint exists. Imports whose path is computed at runtime
+/// are not seen, so a complete report means the listed imports will load, not
+/// that the document compiles.
+///
+/// # Example
+///
+/// ```rust
+/// use std::collections::HashMap;
+/// use dioxus_typst::{check_packages, package_spec, CompileOptions};
+///
+/// let manifest = b"[package]\nname = \"demo\"\nversion = \"0.1.0\"\nentrypoint = \"lib.typ\"";
+/// let files = HashMap::from([
+///     ("typst.toml".to_string(), manifest.to_vec()),
+///     ("lib.typ".to_string(), b"#import \"@preview/helper:1.0.0\"".to_vec()),
+/// ]);
+/// let options = CompileOptions::new().with_package(package_spec("preview", "demo", "0.1.0")?, files);
+///
+/// let source = "#import \"@preview/demo:0.1.0\"\n#import \"@preview/absent:0.2.0\": *";
+/// let report = check_packages(source, &options);
+/// # assert_eq!(report.resolved, [package_spec("preview", "demo", "0.1.0")?]);
+/// # let missing: Vec<String> = report.unresolved.iter().map(|(spec, _)| spec.to_string()).collect();
+/// # assert_eq!(missing, ["@preview/absent:0.2.0", "@preview/helper:1.0.0"]);
+/// if !report.is_complete() {
+///     for (spec, reason) in &report.unresolved {
+///         eprintln!("{spec}: {reason}");
+///     }
+/// }
+/// # assert!(check_packages("= Plain", &options).is_complete());
+/// # Ok::<(), dioxus_typst::CompileError>(())
+/// ```
+pub fn check_packages(source: &str, options: &CompileOptions) -> PackageReport {
+    fn targets(node: &SyntaxNode, found: &mut Vec<String>) {
+        let target = match (
+            node.cast::<ast::ModuleImport>(),
+            node.cast::<ast::ModuleInclude>(),
+        ) {
+            (Some(import), _) => Some(import.source()),
+            (_, Some(include)) => Some(include.source()),
+            _ => None,
+        };
+        if let Some(ast::Expr::Str(path)) = target {
+            found.push(path.get().to_string());
+        }
+        for child in node.children() {
+            targets(child, found);
+        }
+    }
+
+    let world = CompileWorld::new(source, options);
+    let mut report = PackageReport::default();
+    let mut seen = vec![world.main()];
+    let mut queue = vec![world.main()];
+    while let Some(id) = queue.pop() {
+        let Ok(file) = world.source(id) else {
+            continue;
+        };
+        let mut found = Vec::new();
+        targets(file.root(), &mut found);
+        for target in found {
+            let next = match target.strip_prefix('@') {
+                None => id.join(&target),
+                Some(_) => {
+                    let Ok(spec) = target.parse::<PackageSpec>() else {
+                        continue;
+                    };
+                    if report.resolved.contains(&spec)
+                        || report.unresolved.iter().any(|(other, _)| *other == spec)
+                    {
+                        continue;
+                    }
+                    match resolve_package(&world, &spec) {
+                        Ok(entrypoint) => {
+                            report.resolved.push(spec);
+                            entrypoint
+                        }
+                        Err(reason) => {
+                            report.unresolved.push((spec, reason));
+                            continue;
+                        }
+                    }
+                }
+            };
+            if !seen.contains(&next) {
+                seen.push(next);
+                queue.push(next);
+            }
+        }
+    }
+    report
+}
+
+/// Loads a package's manifest and checks that its entrypoint exists, returning
+/// the entrypoint's file.
+fn resolve_package(world: &CompileWorld, spec: &PackageSpec) -> Result<FileId, String> {
+    let manifest = world
+        .get_package_file(spec, "/typst.toml")
+        .map_err(|e| e.to_string())?;
+    let manifest: PackageManifest = std::str::from_utf8(&manifest)
+        .map_err(|_| "typst.toml is not valid UTF-8".to_string())
+        .and_then(|text| {
+            toml::from_str(text).map_err(|e| format!("invalid typst.toml: {}", e.message()))
+        })?;
+    manifest.validate(spec).map_err(|e| e.to_string())?;
+    let entrypoint = FileId::new(
+        Some(spec.clone()),
+        VirtualPath::new(manifest.package.entrypoint.as_str()),
+    );
+    world.source(entrypoint).map_err(|e| e.to_string())?;
+    Ok(entrypoint)
+}
+
+/// Compilations between automatic cache evictions, or `0` if disabled.
+static EVICT_EVERY: AtomicUsize = AtomicUsize::new(0);
+/// The `max_age` automatic evictions pass to [`evict_cache`].
+static EVICT_MAX_AGE: AtomicUsize = AtomicUsize::new(0);
+/// Compilations since the last automatic eviction.
+static COMPILATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Drops entries from Typst's memoization cache that have gone unused.
+///
+/// Typst memoizes parsing, evaluation, and layout in a process-wide cache, so
+/// recompiling a document after a small edit only redoes the work the edit
+/// affects. Nothing is ever removed from that cache on its own. An app that
+/// keeps editing one document holds on to stale intermediate results, and a
+/// server rendering many different documents grows without bound.
+///
+/// Each call ages every entry by one and removes those whose age reaches
+/// `max_age`; an entry's age resets whenever a compilation reuses it. Calling
+/// this after every compilation with a `max_age` of around `10`, as Typst's own
+/// watch mode does, keeps recently used results warm. A `max_age` of `0`
+/// empties the cache, making the next compilation start from scratch. See
+/// [`evict_cache_every`] to have this done automatically.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::{compile_html, evict_cache, CompileOptions};
+///
+/// for n in 0..3 {
+///     compile_html(&format!("Report {n}"), &CompileOptions::new()).unwrap();
+///     evict_cache(10);
+/// }
+/// ```
+pub fn evict_cache(max_age: usize) {
+    typst::comemo::evict(max_age);
+}
+
+/// Calls [`evict_cache`] with `max_age` after every `compilations`
+/// compilations, across all threads.
+///
+/// Pass `0` compilations to turn automatic eviction back off, which is the
+/// default. Every compilation function in this crate counts, including
+/// [`compile_with_world`].
+///
+/// # Example
+///
+/// ```rust
+/// // A long-running server: age the cache after each render, forgetting
+/// // anything not reused within the last ten.
+/// dioxus_typst::evict_cache_every(1, 10);
+/// ```
+pub fn evict_cache_every(compilations: usize, max_age: usize) {
+    EVICT_MAX_AGE.store(max_age, Ordering::Relaxed);
+    EVICT_EVERY.store(compilations, Ordering::Relaxed);
+    COMPILATIONS.store(0, Ordering::Relaxed);
+}
+
+/// Counts a finished compilation and evicts the cache when it is due.
+fn count_compilation() {
+    CACHE_STATS.compilations.fetch_add(1, Ordering::Relaxed);
+    let every = EVICT_EVERY.load(Ordering::Relaxed);
+    if every == 0 {
+        return;
+    }
+    if COMPILATIONS.fetch_add(1, Ordering::Relaxed) + 1 >= every {
+        COMPILATIONS.store(0, Ordering::Relaxed);
+        evict_cache(EVICT_MAX_AGE.load(Ordering::Relaxed));
+    }
+}
+
+/// Counters for the caches this crate keeps, see [`cache_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Compilations run by any function in this crate.
+    pub compilations: usize,
+    /// Worlds that reused an already built standard library.
+    pub library_hits: usize,
+    /// Worlds that had to build a standard library, either the first with its
+    /// features or one for a document with `sys.inputs`.
+    pub library_misses: usize,
+    /// Package files found in a package source.
+    pub package_hits: usize,
+    /// Package files no package source could provide.
+    pub package_misses: usize,
+}
+
+/// Atomic counterparts of the [`CacheStats`] fields.
+struct CacheCounters {
+    compilations: AtomicUsize,
+    library_hits: AtomicUsize,
+    library_misses: AtomicUsize,
+    package_hits: AtomicUsize,
+    package_misses: AtomicUsize,
+}
+
+/// The process-wide counters behind [`cache_stats`].
+static CACHE_STATS: CacheCounters = CacheCounters {
+    compilations: AtomicUsize::new(0),
+    library_hits: AtomicUsize::new(0),
+    library_misses: AtomicUsize::new(0),
+    package_hits: AtomicUsize::new(0),
+    package_misses: AtomicUsize::new(0),
+};
+
+/// Returns how often the caches kept by this crate were hit and missed since
+/// the process started, across all threads.
+///
+/// Sample it periodically and compare with the previous sample to get rates.
+/// Many package misses point at a package that should be preloaded or added
+/// to a [`PackageSource::Directory`]; library misses that keep growing mean
+/// documents pass `sys.inputs`, which need a library of their own. Packages
+/// are never downloaded, so there is no download count. Typst's memoization
+/// cache doesn't report its hit rate; see [`evict_cache`] for sizing it.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::{cache_stats, compile_html, CompileOptions};
+///
+/// let before = cache_stats();
+/// compile_html("Hello", &CompileOptions::new()).unwrap();
+/// let after = cache_stats();
+/// println!("{} compilations so far", after.compilations);
+/// # assert!(after.compilations > before.compilations);
+/// # assert!(after.library_hits + after.library_misses > before.library_hits + before.library_misses);
+/// # let _ = compile_html(r#"#import "@preview/missing:0.1.0": *"#, &CompileOptions::new());
+/// # assert!(cache_stats().package_misses > after.package_misses);
+/// ```
+pub fn cache_stats() -> CacheStats {
+    let load = |counter: &AtomicUsize| counter.load(Ordering::Relaxed);
+    CacheStats {
+        compilations: load(&CACHE_STATS.compilations),
+        library_hits: load(&CACHE_STATS.library_hits),
+        library_misses: load(&CACHE_STATS.library_misses),
+        package_hits: load(&CACHE_STATS.package_hits),
+        package_misses: load(&CACHE_STATS.package_misses),
+    }
+}
+
+/// Returns the version of Typst this crate was built against, such as `"0.14.2"`.
+///
+/// `typst` and `typst-html` are released in lockstep, so this is also the
+/// version of the HTML exporter. Because HTML export is experimental and its
+/// output changes between releases, this is worth including in bug reports.
+///
+/// It is also the only language version: Typst has no edition or
+/// compatibility setting, and its library [`Feature`]s only switch on
+/// experimental additions like HTML export. A document written for an older
+/// release compiles with this version's syntax and standard library, so
+/// pin the crate to keep rendering it unchanged.
+///
+/// # Example
+///
+/// ```rust
+/// let version = dioxus_typst::typst_version();
+/// # assert!(version.starts_with("0."));
+/// # let options = dioxus_typst::CompileOptions::new();
+/// # let html = dioxus_typst::compile_html("#sys.version", &options).unwrap();
+/// # assert_eq!(html, format!("<p>{version}</p>"));
+/// ```
+pub fn typst_version() -> &'static str {
+    static VERSION: OnceLock<String> = OnceLock::new();
+    VERSION.get_or_init(|| PackageVersion::compiler().to_string())
+}
+
+/// Compiles Typst source and extracts document metadata.
+///
+/// Still compiles the document, so errors may occur during this process
+///
+/// # Example:
+///
+/// ```rust
+/// use dioxus_typst::{extract_metadata, CompileOptions};
+///
+/// let source = r#"
+/// #set document(
+///     title: "Sample Document",
+///     author: "John Doe",
+///     description: "A brief description of the document.",
+///     keywords: ["typst", "rust", "dioxus"],
+///     date: datetime(year: 2026, month: 1, day: 1),
+/// )
+/// let metadata = extract_metadata(source, &CompileOptions::new()).unwrap();
+/// # assert_eq!(metadata.title.unwrap(), "Sample Document");
+/// # assert_eq!(metadata.authors, vec!["John Doe"]);
+/// # assert_eq!(metadata.description.unwrap(), "A brief description of the document.");
+/// # assert_eq!(metadata.keywords, vec!["typst", "rust", "dioxus"]);
+/// # assert_eq!(metadata.date.unwrap().to_string(), "2026-01-01");
+/// "#;
+/// ```
+pub fn extract_metadata(
+    source: &str,
+    options: &CompileOptions,
+) -> Result<DocumentMetadata, CompileError> {
+    let world = CompileWorld::new(source, options);
+    let document = world.compile::<HtmlDocument>().output?;
+    Ok(document_metadata(&document.info))
+}
+
+/// Converts Typst's document info into [`DocumentMetadata`].
+fn document_metadata(doc_info: &DocumentInfo) -> DocumentMetadata {
+    let title = doc_info.title.as_ref().map(|t| t.to_string());
+
+    let authors = doc_info.author.iter().map(|a| a.to_string()).collect();
+
+    let description = doc_info.description.as_ref().map(|d| d.to_string());
+
+    let keywords = doc_info.keywords.iter().map(|k| k.to_string()).collect();
+
+    let date = match &doc_info.date {
+        Smart::Custom(Some(datetime)) => chrono::NaiveDate::from_ymd_opt(
+            datetime.year().unwrap_or(chrono::Local::now().year()),
+            datetime
+                .month()
+                .unwrap_or(chrono::Local::now().month() as u8)
+                .into(),
+            datetime
+                .day()
+                .unwrap_or(chrono::Local::now().day() as u8)
+                .into(),
+        ),
+        _ => None,
+    };
+
+    DocumentMetadata {
+        title,
+        authors,
+        description,
+        keywords,
+        date,
+    }
+}
+
+/// Compiles Typst source and returns the elements matching a selector, like
+/// `typst query`.
+///
+/// The selector is Typst code, such as `"<slug>"` for a label, `"metadata"`,
+/// or `"heading.where(level: 1)"`. Matched `#metadata(..)` elements are
+/// returned as the value they hold, so a document can carry structured data
+/// for the app to read; any other element is returned as its content.
+///
+/// # Errors
+///
+/// Returns an error if the document fails to compile, or if the selector is
+/// invalid or can't be queried.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::{query, CompileOptions, Value};
+/// use dioxus_typst::typst::foundations::IntoValue;
+///
+/// let source = r#"
+/// #metadata((slug: "getting-started", order: 2)) <page>
+/// = Getting Started
+/// "#;
+/// let values = query(source, &CompileOptions::new(), "<page>").unwrap();
+/// if let Some(Value::Dict(page)) = values.first() {
+///     let slug = page.get("slug").unwrap();
+///     # assert_eq!(*slug, "getting-started".into_value());
+/// }
+/// # assert_eq!(values.len(), 1);
+/// # assert_eq!(query(source, &CompileOptions::new(), "heading").unwrap().len(), 1);
+/// # assert!(query(source, &CompileOptions::new(), "1 +").is_err());
+/// # assert!(query(source, &CompileOptions::new(), "12").is_err());
+/// ```
+pub fn query(
+    source: &str,
+    options: &CompileOptions,
+    selector: &str,
+) -> Result<Vec<Value>, CompileError> {
+    let world = CompileWorld::new(source, options);
+    let document = world.compile::<HtmlDocument>().output?;
+
+    let dyn_world: &dyn World = &world;
+    let value = (typst::ROUTINES.eval_string)(
+        &typst::ROUTINES,
+        dyn_world.track(),
+        Sink::new().track_mut(),
+        selector,
+        Span::detached(),
+        SyntaxMode::Code,
+        Scope::default(),
+    )
+    .map_err(|errors| world.error(&errors))?;
+    let selector = LocatableSelector::from_value(value)
+        .map_err(|errors| CompileError::Typst(errors.message().to_string()))?;
+
+    Ok(document
+        .introspector
+        .query(&selector.0)
+        .into_iter()
+        .map(|content| match content.to_packed::<MetadataElem>() {
+            Some(metadata) => metadata.value.clone(),
+            None => content.into_value(),
+        })
+        .collect())
+}
+
+/// Compiles Typst source and returns the HTML together with its warnings,
+/// metadata, and outline.
+///
+/// This is [`compile_html`] and [`extract_metadata`] in one pass, plus what
+/// neither of them returns: the warnings, and the headings for building a
+/// table of contents. The [`Typst`] component only needs
+/// [`html`](CompiledDocument::html).
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::{compile, CompileOptions, OutlineEntry};
+///
+/// let source = r#"
+/// #set document(title: "Guide")
+/// = Install <install>
+/// == From source
+/// #text(font: "Missing Sans")[Text]
+/// #block(fill: gray)[Boxed]
+/// #rect()
+/// "#;
+/// let document = compile(source, &CompileOptions::new()).unwrap();
+/// # assert!(document.html.contains("<h2>Install</h2>"));
+/// # assert_eq!(document.metadata.title.as_deref(), Some("Guide"));
+/// # assert!(
+/// #     document
+/// #         .warnings
+/// #         .iter()
+/// #         .any(|w| w.message == "unknown font family: missing sans")
+/// # );
+/// // Content the HTML export can't represent is reported instead of vanishing.
+/// # let messages: Vec<_> = document.warnings.iter().map(|w| w.message.as_str()).collect();
+/// # assert!(messages.contains(&"`font` of text was ignored during HTML export"));
+/// # assert!(messages.contains(&"`fill` of block was ignored during HTML export"));
+/// # assert!(messages.contains(&"rect was ignored during HTML export"));
+/// # let rect = document.warnings.iter().find(|w| w.message.starts_with("rect")).unwrap();
+/// # assert_eq!(rect.hints, ["wrap it in `html.frame` to keep it as an SVG"]);
+/// # assert_eq!(&source[rect.range.clone().unwrap()], "rect()");
+/// # let framed = r#"#html.frame(box(fill: red, width: 1cm, height: 1cm))
+/// # #context if target() == "paged" { block(fill: red)[x] }
+/// # #include "chapter.typ""#;
+/// # let options = CompileOptions::new().with_file("chapter.typ", b"#block(inset: 1em)[x]".to_vec());
+/// # let framed = compile(framed, &options).unwrap();
+/// # let messages: Vec<_> = framed.warnings.iter().map(|w| w.message.as_str()).collect();
+/// # assert!(!messages.iter().any(|m| m.contains("of box") || m.contains("`fill`")), "{messages:?}");
+/// # let chapter = framed.warnings.iter().find(|w| w.message.contains("`inset` of block")).unwrap();
+/// # assert_eq!(chapter.file.as_deref(), Some("/chapter.typ"));
+/// # let lenient = CompileOptions::new().with_lenient_references(true).with_math_alt_text(true);
+/// # assert!(compile("Hi", &lenient).unwrap().warnings.iter().all(|w| !w.message.contains("of ")));
+/// for entry in &document.outline {
+///     println!("{}{}", "  ".repeat(entry.level - 1), entry.title);
+/// }
+/// # assert_eq!(
+/// #     document.outline,
+/// #     [
+/// #         OutlineEntry { level: 1, title: "Install".into(), label: Some("install".into()) },
+/// #         OutlineEntry { level: 2, title: "From source".into(), label: None },
+/// #     ],
+/// # );
+/// ```
+pub fn compile(source: &str, options: &CompileOptions) -> Result<CompiledDocument, CompileError> {
+    let world = CompileWorld::new(source, options);
+    let warned = world.compile::<HtmlDocument>();
+    let document = warned.output?;
+    let page_style = world.page_style();
+    let html = encode_html(&document).map_err(|errors| world.error(&errors))?;
+
+    let outline = document
+        .introspector
+        .query(&Selector::Elem(HeadingElem::ELEM, None))
+        .iter()
+        .filter_map(|content| {
+            let heading = content.to_packed::<HeadingElem>()?;
+            heading.outlined().then(|| OutlineEntry {
+                level: heading.level().get(),
+                title: heading.body.plain_text().to_string(),
+                label: content.label().map(|l| l.resolve().to_string()),
+            })
+        })
+        .collect();
+
+    Ok(CompiledDocument {
+        html: finish_html(body_fragment(&html).to_string(), options),
+        warnings: warned
+            .warnings
+            .iter()
+            .map(|w| world.diagnostic(w))
+            .collect(),
+        metadata: document_metadata(&document.info),
+        outline,
+        page_style,
+    })
+}
+
+/// A description of a compiled document, for writing next to its HTML.
+///
+/// Requires the `serde` feature.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct DocumentSummary {
+    /// The metadata set with `#set document(..)`.
+    pub metadata: DocumentMetadata,
+    /// Warnings Typst reported while compiling to HTML.
+    pub warnings: Vec<Diagnostic>,
+    /// The document's headings, as in [`CompiledDocument::outline`].
+    pub outline: Vec<OutlineEntry>,
+    /// How many pages the document has when laid out for paged export, with
+    /// the page setup from the options.
+    pub pages: usize,
+}
+
+/// Compiles Typst source and describes the result as JSON.
+///
+/// The JSON is a serialized [`DocumentSummary`]: the document's metadata,
+/// its warnings, its outline, and its page count in paged form, ready to be
+/// written as a sidecar file or logged. Counting pages takes a paged
+/// compilation on top of the HTML one.
+///
+/// Requires the `serde` feature.
+///
+/// # Example
+///
+/// ```rust
+/// use dioxus_typst::{summary_json, CompileOptions};
+///
+/// let source = "#set document(title: \"Guide\")\n= Install\n#pagebreak()\n= Use";
+/// let json = summary_json(source, &CompileOptions::new()).unwrap();
+/// # assert!(json.starts_with(r#"{"metadata":{"title":"Guide","authors":[]"#), "{json}");
+/// # assert!(json.contains(r#""outline":[{"level":1,"title":"Install","label":null},"#));
+/// # assert!(json.contains(r#""file":"/main.typ","range":{"start":41,"end":52}"#));
+/// # assert!(json.ends_with(r#""pages":2}"#));
+/// let path = std::env::temp_dir().join("guide.json");
+/// # let path = path.with_file_name(format!("guide-{}.json", std::process::id()));
+/// std::fs::write(&path, json).unwrap();
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+#[cfg(feature = "serde")]
+pub fn summary_json(source: &str, options: &CompileOptions) -> Result<String, CompileError> {
+    let document = compile(source, options)?;
+    let summary = DocumentSummary {
+        metadata: document.metadata,
+        warnings: document.warnings,
+        outline: document.outline,
+        pages: compile_paged(source, options)?.pages.len(),
+    };
+    serde_json::to_string(&summary).map_err(|e| CompileError::Typst(e.to_string()))
+}
@@ -0,0 +1,308 @@
+//! The results of a compilation, which build without the Typst compiler.
+
+use std::ops::Range;
+
+/// Errors that can occur during Typst compilation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompileError {
+    /// Compilation was abandoned or its result could not be used.
+    Typst(String),
+    /// The Typst compiler rejected the document, with the location of each error.
+    Diagnostics(Vec<Diagnostic>),
+}
+
+impl CompileError {
+    /// Returns each error message on its own, in the order Typst reported them.
+    ///
+    /// Use this to render errors however suits the application instead of
+    /// relying on the [`Display`](std::fmt::Display) format.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::{compile_html, CompileOptions};
+    ///
+    /// let error = compile_html("#let\n#(1 +)", &CompileOptions::new()).unwrap_err();
+    /// for message in error.messages() {
+    ///     eprintln!("error: {message}");
+    /// }
+    /// # assert_eq!(error.messages(), ["expected pattern", "expected expression"]);
+    /// # assert_eq!(
+    /// #     error.to_string(),
+    /// #     "Typst compilation errors:\n- expected pattern\n- expected expression",
+    /// # );
+    /// ```
+    pub fn messages(&self) -> Vec<&str> {
+        match self {
+            CompileError::Typst(msg) => vec![msg.as_str()],
+            CompileError::Diagnostics(diagnostics) => {
+                diagnostics.iter().map(|d| d.message.as_str()).collect()
+            }
+        }
+    }
+}
+
+/// A single error is shown inline; several are shown as a bulleted list, one
+/// message per line.
+impl std::fmt::Display for CompileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.messages().as_slice() {
+            [message] => write!(f, "Typst compilation error: {message}"),
+            messages => {
+                write!(f, "Typst compilation errors:")?;
+                for message in messages {
+                    write!(f, "\n- {message}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+/// An error or warning reported by the Typst compiler, with its location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diagnostic {
+    /// The message.
+    pub message: String,
+    /// Suggestions for fixing the error.
+    pub hints: Vec<String>,
+    /// The path of the file the error points into, if it has a location,
+    /// prefixed with the package for files inside one, such as
+    /// `@preview/example:0.1.0/lib.typ`.
+    pub file: Option<String>,
+    /// The byte range of the error within `file`.
+    ///
+    /// For the main document, the range is relative to the source passed in,
+    /// not counting any preamble. It is `None` when the error lies inside the
+    /// preamble or postamble, or has no location at all.
+    pub range: Option<Range<usize>>,
+}
+
+impl Diagnostic {
+    /// Resolves the diagnostic's byte range to a line and column in `source`.
+    ///
+    /// Pass the same source string that was compiled (or, for errors in other
+    /// files, that file's contents). Returns `None` if the diagnostic has no
+    /// range or the range doesn't fit `source`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::{compile_html, CompileError, CompileOptions};
+    ///
+    /// let source = "= Title\nSome #undefined text";
+    /// let Err(CompileError::Diagnostics(diagnostics)) = compile_html(source, &CompileOptions::new())
+    /// else {
+    ///     panic!("expected diagnostics");
+    /// };
+    /// let location = diagnostics[0].locate(source).unwrap();
+    /// # assert_eq!(diagnostics[0].range, Some(14..23));
+    /// # assert_eq!((location.line, location.column), (2, 7));
+    /// # assert_eq!(location.snippet, "Some #undefined text");
+    /// # let options = CompileOptions::new().with_preamble("#set text(size: 12pt)");
+    /// # let Err(CompileError::Diagnostics(diagnostics)) = compile_html(source, &options) else {
+    /// #     panic!("expected diagnostics");
+    /// # };
+    /// # assert_eq!(diagnostics[0].range, Some(14..23));
+    /// ```
+    pub fn locate(&self, source: &str) -> Option<SourceLocation> {
+        let range = self.range.clone()?;
+        let before = source.get(..range.start)?;
+        source.get(range)?;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[line_start..]
+            .find('\n')
+            .map_or(source.len(), |i| line_start + i);
+        Some(SourceLocation {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            snippet: source[line_start..line_end]
+                .trim_end_matches('\r')
+                .to_string(),
+        })
+    }
+}
+
+/// A position in source text, as resolved by [`Diagnostic::locate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// The one-based line number.
+    pub line: usize,
+    /// The one-based column, counted in characters.
+    pub column: usize,
+    /// The full text of the line.
+    pub snippet: String,
+}
+
+/// The page and text colors a document sets, which HTML export ignores.
+///
+/// Typst drops `#set page(fill: ..)` when exporting HTML, since there are no
+/// pages, so a dark document would render on the app's background. The
+/// [`Typst`](crate::Typst) component applies these colors to its wrapper
+/// `<div>` with [`css`](Self::css); [`compile`](crate::compile) returns them
+/// for other hosts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PageStyle {
+    /// The page fill as a CSS hex color, if it is set to a solid color.
+    pub fill: Option<String>,
+    /// The text fill as a CSS hex color, if it is a solid color other than
+    /// Typst's default black.
+    pub text: Option<String>,
+}
+
+impl PageStyle {
+    /// Returns inline CSS applying the colors, or an empty string if there
+    /// are none.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::{compile, CompileOptions};
+    ///
+    /// let source = "#set page(fill: rgb(\"#1e1e2e\"))\n#set text(fill: white)\nHello";
+    /// let document = compile(source, &CompileOptions::new()).unwrap();
+    /// let css = document.page_style.css();
+    /// # assert_eq!(css, "background-color: #1e1e2e; color: #ffffff");
+    /// # assert_eq!(compile("Hello", &CompileOptions::new()).unwrap().page_style.css(), "");
+    /// # let gradient = "#set page(fill: gradient.linear(red, blue))\nHello";
+    /// # assert_eq!(compile(gradient, &CompileOptions::new()).unwrap().page_style.fill, None);
+    /// # let counted = "#set page(fill: black)\n#metadata(1)\n#context query(metadata).len()";
+    /// # let html = dioxus_typst::compile_html(counted, &CompileOptions::new()).unwrap();
+    /// # assert_eq!(html, "<p>1</p>");
+    /// # assert_eq!(compile(counted, &CompileOptions::new()).unwrap().html, html);
+    /// # // Templates applied with `#show:` keep their output and report their colors.
+    /// # let template = "#let dark(body) = { set page(fill: black); html.elem(\"article\", body) }\n\
+    /// #     #show: dark\nHello";
+    /// # let html = dioxus_typst::compile_html(template, &CompileOptions::new()).unwrap();
+    /// # assert_eq!(html, "<article>Hello</article>");
+    /// # let document = compile(template, &CompileOptions::new()).unwrap();
+    /// # assert_eq!(document.html, html);
+    /// # assert_eq!(document.page_style.fill.as_deref(), Some("#000000"));
+    /// ```
+    pub fn css(&self) -> String {
+        let fill = self
+            .fill
+            .as_ref()
+            .map(|fill| format!("background-color: {fill}"));
+        let text = self.text.as_ref().map(|text| format!("color: {text}"));
+        fill.into_iter().chain(text).collect::<Vec<_>>().join("; ")
+    }
+}
+
+/// Metadata extracted from a Typst document.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DocumentMetadata {
+    pub title: Option<String>,
+    pub authors: Vec<String>,
+    pub description: Option<String>,
+    pub keywords: Vec<String>,
+    pub date: Option<chrono::NaiveDate>,
+}
+
+/// A heading in a document's outline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OutlineEntry {
+    /// The nesting level, starting at 1 for top-level headings.
+    pub level: usize,
+    /// The heading's text, without numbering or formatting.
+    pub title: String,
+    /// The heading's label, if it has one.
+    pub label: Option<String>,
+}
+
+/// Everything one compilation produces, returned by [`compile`](crate::compile).
+///
+/// With the `serde` feature, a document can be compiled once at build time,
+/// saved with `to_json`, and loaded again at runtime with `from_json` without
+/// compiling anything.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompiledDocument {
+    /// The HTML fragment, as returned by [`compile_html`](crate::compile_html).
+    pub html: String,
+    /// Warnings Typst reported while compiling, including content and
+    /// styling that the HTML export left out.
+    pub warnings: Vec<Diagnostic>,
+    /// The metadata set with `#set document(..)`.
+    pub metadata: DocumentMetadata,
+    /// The document's headings, in order, excluding those with `outlined: false`.
+    pub outline: Vec<OutlineEntry>,
+    /// The page and text colors the document sets.
+    pub page_style: PageStyle,
+}
+
+#[cfg(feature = "serde")]
+impl CompiledDocument {
+    /// Serializes the document as JSON, to be saved and loaded later with
+    /// [`from_json`](Self::from_json).
+    ///
+    /// The JSON is plain data: the HTML, the warnings, the metadata, the
+    /// outline, and the page colors. An app that only displays precompiled
+    /// documents can load it with this crate's default `compiler` feature
+    /// turned off, so the Typst compiler isn't built at all.
+    ///
+    /// Requires the `serde` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CompileError::Typst`] if the document can't be serialized.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus_typst::{compile, CompileOptions};
+    ///
+    /// let source = "#set document(title: \"Guide\")\n= Install";
+    /// let document = compile(source, &CompileOptions::new()).unwrap();
+    /// let path = std::env::temp_dir().join("guide.typst.json");
+    /// # let path = path.with_file_name(format!("guide-{}.typst.json", std::process::id()));
+    /// std::fs::write(&path, document.to_json().unwrap()).unwrap();
+    /// # let json = document.to_json().unwrap();
+    /// # assert!(json.starts_with(r#"{"html":"<h2>Install</h2>","warnings":[{"#), "{json}");
+    /// # assert!(json.ends_with(r#""page_style":{"fill":null,"text":null}}"#), "{json}");
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn to_json(&self) -> Result<String, CompileError> {
+        serde_json::to_string(self)
+            .map_err(|e| CompileError::Typst(format!("failed to serialize compiled document: {e}")))
+    }
+
+    /// Loads a document saved with [`to_json`](Self::to_json).
+    ///
+    /// Returns [`CompileError::Typst`] if `json` is not a saved document.
+    ///
+    /// Requires the `serde` feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dioxus::prelude::*;
+    /// use dioxus_typst::CompiledDocument;
+    ///
+    /// #[component]
+    /// fn Precompiled(json: String) -> Element {
+    ///     match CompiledDocument::from_json(&json) {
+    ///         Ok(document) => rsx! {
+    ///             div { style: document.page_style.css(), dangerous_inner_html: "{document.html}" }
+    ///         },
+    ///         Err(e) => rsx! { p { "{e}" } },
+    ///     }
+    /// }
+    /// # use dioxus_typst::{compile, CompileOptions};
+    /// # let source = "#set page(fill: black)\n= Intro\n#text(stroke: red)[Hi]";
+    /// # let compiled = compile(source, &CompileOptions::new()).unwrap();
+    /// # assert!(compiled.warnings.iter().any(|w| w.file.is_some()));
+    /// # assert_eq!(CompiledDocument::from_json(&compiled.to_json().unwrap()).unwrap(), compiled);
+    /// # assert!(CompiledDocument::from_json("{}").is_err());
+    /// ```
+    pub fn from_json(json: &str) -> Result<Self, CompileError> {
+        serde_json::from_str(json)
+            .map_err(|e| CompileError::Typst(format!("invalid compiled document: {e}")))
+    }
+}
@@ -0,0 +1,9 @@
+```rust
+fn main() {
+    println!("Hello");
+}
+```
+
+```
+plain block
+```
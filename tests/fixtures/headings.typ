@@ -0,0 +1,9 @@
+#set heading(numbering: "1.1")
+
+= Introduction <intro>
+
+Some *bold*, _emphasized_, and `inline code` text.
+
+== Background
+
+See @intro for the start.
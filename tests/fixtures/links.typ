@@ -0,0 +1,8 @@
+Visit #link("https://typst.app")[Typst] or https://example.com.
+
+#figure(
+  rect(width: 1cm, height: 1cm),
+  caption: [A square.],
+) <square>
+
+@square shows a square.
@@ -0,0 +1,8 @@
+- First
+- Second
+  - Nested
+
++ One
++ Two
+
+/ Term: Its description.
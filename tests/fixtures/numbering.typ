@@ -0,0 +1,19 @@
+#set heading(numbering: "1.1.a")
+
+= Setup
+
+== Requirements
+
+=== Hardware
+
+=== Software
+
+== Installation
+
+= Usage
+
+#heading(numbering: none, level: 2)[Notes]
+
+== Configuration
+
+=== Files
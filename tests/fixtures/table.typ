@@ -0,0 +1,6 @@
+#table(
+  columns: 2,
+  table.header[Name][Value],
+  [Width], [10],
+  [Height], [20],
+)
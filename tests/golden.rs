@@ -0,0 +1,78 @@
+//! Compares the HTML of every fixture in `tests/fixtures` with its committed
+//! golden output, so changes in Typst's HTML export show up on dependency bumps.
+//!
+//! Each `name.typ` is compiled with default options and compared against
+//! `name.html`. After checking that a change in output is intended, rewrite the
+//! golden files with:
+//!
+//! ```sh
+//! DIOXUS_TYPST_BLESS=1 cargo test --test golden
+//! ```
+
+use std::{fs, path::Path};
+
+use dioxus_typst::{CompileOptions, compile_html};
+
+#[test]
+fn fixtures_match_golden_html() {
+    let bless = std::env::var_os("DIOXUS_TYPST_BLESS").is_some();
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+
+    let mut fixtures: Vec<_> = fs::read_dir(&dir)
+        .expect("tests/fixtures exists")
+        .map(|entry| entry.expect("fixture is readable").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "typ"))
+        .collect();
+    fixtures.sort();
+    assert!(!fixtures.is_empty(), "no fixtures in {}", dir.display());
+
+    let mut failures = Vec::new();
+    for fixture in &fixtures {
+        let source = fs::read_to_string(fixture).expect("fixture is UTF-8");
+        let html = match compile_html(&source, &CompileOptions::new()) {
+            Ok(html) => html + "\n",
+            Err(e) => {
+                failures.push(format!("{}: {e}", fixture.display()));
+                continue;
+            }
+        };
+
+        let golden = fixture.with_extension("html");
+        if bless {
+            fs::write(&golden, &html).expect("golden file is writable");
+            continue;
+        }
+        let Ok(expected) = fs::read_to_string(&golden) else {
+            failures.push(format!(
+                "{}: missing, run with DIOXUS_TYPST_BLESS=1",
+                golden.display()
+            ));
+            continue;
+        };
+        if let Some((line, (want, got))) = expected
+            .lines()
+            .zip(html.lines())
+            .enumerate()
+            .find(|(_, (want, got))| want != got)
+        {
+            failures.push(format!(
+                "{}:{}\n  expected: {want}\n  actual:   {got}",
+                golden.display(),
+                line + 1,
+            ));
+        } else if expected.lines().count() != html.lines().count() {
+            failures.push(format!(
+                "{}: expected {} lines, got {}",
+                golden.display(),
+                expected.lines().count(),
+                html.lines().count(),
+            ));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "HTML output changed; if intended, run with DIOXUS_TYPST_BLESS=1\n\n{}",
+        failures.join("\n\n"),
+    );
+}